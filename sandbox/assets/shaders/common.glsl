@@ -0,0 +1,3 @@
+float map(float val, float in_min, float in_max, float out_min, float out_max) {
+	return out_min + (out_max - out_min) * (val - in_min) / (in_max - in_min);
+}
@@ -2,25 +2,27 @@
 #version 330 core
 
 layout(location = 0) in vec3 in_pos;
+// per instance
+layout(location = 1) in vec2 in_cell;
+layout(location = 2) in vec4 in_color;
+
+out vec4 v_color;
 
-uniform uint u_x;
-uniform uint u_y;
 uniform uint u_cols;
 uniform uint u_rows;
 
-float map(float val, float in_min, float in_max, float out_min, float out_max) {
-	return out_min + (out_max - out_min) * (val - in_min) / (in_max - in_min);
-}
+#include "common.glsl"
 
 void main() {
-	float x = map(u_x, 0.0, float(u_cols), -1.0, 1.0);
-	float y = map(u_y, 0.0, float(u_rows), -1.0, 1.0);
+	float x = map(in_cell.x, 0.0, float(u_cols), -1.0, 1.0);
+	float y = map(in_cell.y, 0.0, float(u_rows), -1.0, 1.0);
 
 	gl_Position = vec4(
 		x + in_pos[0] / float(u_cols) * 2,
 		y + in_pos[1] / float(u_rows) * 2,
 		0.0, 1.0
 	);
+	v_color = in_color;
 }
 
 #type fragment
@@ -28,8 +30,8 @@ void main() {
 
 layout(location = 0) out vec4 out_color;
 
-uniform vec4 u_color;
+in vec4 v_color;
 
 void main() {
-	out_color = u_color;
+	out_color = v_color;
 }
@@ -84,7 +84,7 @@ impl Overlay for ExampleOverlay {
 			self.rotation -= speed * dt.as_secs_f32();
 		}
 	}
-	fn on_render(&mut self, renderer: &mut dyn Renderer) {
+	fn on_render(&mut self, renderer: &mut dyn Renderer, _alpha: whm::Float) {
 		let elapsed = self.start_time.elapsed().as_secs_f32();
 		renderer.set_clear_color((
 			elapsed.tan(),
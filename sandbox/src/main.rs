@@ -7,36 +7,38 @@ mod snake_layer;
 use snake_layer::SnakeOverlay;
 
 fn main() {
-	wrath::init(Application::new(), wrath::EngineProps {
-		window_props: wrath::WindowProps {
-			title: "Curls of Lordraft".into(),
-			size: (800, 500),
-		}
-	});
+	wrath::init(
+		Application::new(),
+		wrath::EngineProps::new("Curls of Lordraft", (800, 500)),
+	)
+	.expect("Could not start the engine");
 }
 
 struct Application {
 	ex_overlay: wrath::OverlayHandle,
+	debug_ui: wrath::OverlayHandle,
 }
 
 impl Application {
 	fn new() -> Self {
 		Self {
 			ex_overlay: wrath::OverlayHandle::none(),
+			debug_ui: wrath::OverlayHandle::none(),
 		}
 	}
 }
 
 impl wrath::CallbackHandler for Application {
 	fn on_create(&mut self, engine: &mut wrath::Engine) {
-		unsafe { gl::Viewport(0, 0, 800, 500) };
-
-		self.ex_overlay = engine.push_overlay_front(box SnakeOverlay::new());
+		let debug_ui = wrath::DebugUiOverlay::new();
+		self.ex_overlay = engine.push_overlay_front(box SnakeOverlay::new(debug_ui.ui()));
+		self.debug_ui = engine.push_overlay_front(box debug_ui);
 	}
 	fn on_update(&mut self, _engine: &mut wrath::Engine) {
 		// do shit
 	}
 	fn on_exit(&mut self, engine: &mut wrath::Engine) {
+		engine.remove_overlay(self.debug_ui);
 		engine.remove_overlay(self.ex_overlay);
 	}
 }
@@ -3,12 +3,30 @@ use std::path::Path;
 
 const COLS: u32 = 16;
 const ROWS: u32 = 10;
-const SNAKE_COLOR: whm::Vector4 = whm::Vector4::new(0.0, 1.0, 0.0, 1.0);
-const FRUIT_COLOR: whm::Vector4 = whm::Vector4::new(1.0, 0.0, 0.0, 1.0);
+const SNAKE_COLOR: [whm::Float; 4] = [0.0, 1.0, 0.0, 1.0];
+const FRUIT_COLOR: [whm::Float; 4] = [1.0, 0.0, 0.0, 1.0];
+const SCORE_COLOR: [whm::Float; 4] = [1.0, 1.0, 1.0, 1.0];
+const GRID_COLOR: [whm::Float; 4] = [1.0, 1.0, 1.0, 0.2];
+
+#[derive(Clone, Copy, wrath::Vertex)]
+#[repr(C)]
+struct CubeVertex {
+	position: [whm::Float; 3],
+}
+
+#[derive(Clone, Copy, wrath::Vertex)]
+#[repr(C)]
+struct CubeInstance {
+	cell: [whm::Float; 2],
+	color: [whm::Float; 4],
+}
 
 pub struct SnakeOverlay {
 	cube_mesh: wrath::MeshHandle,
+	cube_instances: wrath::InstanceBufferHandle,
 	flat_cube_shader: wrath::ShaderHandle,
+	ui: wrath::DebugUi,
+	font: Option<wrath::Font>,
 	elapsed: Duration,
 	frame_time: Duration,
 	snake: Vec<(u32, u32)>,
@@ -18,10 +36,13 @@ pub struct SnakeOverlay {
 }
 
 impl SnakeOverlay {
-	pub fn new() -> Self {
+	pub fn new(ui: wrath::DebugUi) -> Self {
 		Self {
 			cube_mesh: wrath::MeshHandle::none(),
+			cube_instances: wrath::InstanceBufferHandle::none(),
 			flat_cube_shader: wrath::ShaderHandle::none(),
+			ui,
+			font: None,
 			elapsed: Duration::new(0, 0),
 			frame_time: Duration::from_millis(200),
 			snake: vec![(1, 1)],
@@ -84,7 +105,7 @@ impl SnakeOverlay {
 }
 
 impl wrath::Overlay for SnakeOverlay {
-	fn on_update(&mut self, dt: Duration) {
+	fn on_update(&mut self, _dt: Duration) {
 		if !self.turned {
 			if wrath::Button::ArrowRight.is_pressed() {
 				self.dir = 0;
@@ -108,7 +129,19 @@ impl wrath::Overlay for SnakeOverlay {
 			std::process::exit(0);
 		}
 
-		self.elapsed += dt;
+		let ui = self.ui.clone();
+		ui.window("Snake", |ui| {
+			let mut frame_time = self.frame_time.as_secs_f32() * 1000.0;
+			if ui.slider("Frame time", &mut frame_time, 50.0..=500.0) {
+				self.frame_time = Duration::from_secs_f32(frame_time / 1000.0);
+			}
+			if ui.button("Restart") {
+				self.game_over();
+			}
+		});
+	}
+	fn on_fixed_update(&mut self, step: Duration) {
+		self.elapsed += step;
 		if self.elapsed >= self.frame_time {
 			self.step();
 			self.elapsed -= self.frame_time;
@@ -116,24 +149,24 @@ impl wrath::Overlay for SnakeOverlay {
 	}
 	fn on_attach(&mut self, renderer: &mut dyn wrath::Renderer) {
 		self.cube_mesh = renderer.create_mesh(
-			&wrath::Vertices::new(vec![
-				//x    y  (z)
-				1.0, 1.0, 0.0,
-				0.0, 1.0, 0.0,
-				0.0, 0.0, 0.0,
-				1.0, 0.0, 0.0,
-			]),
-			&wrath::BufferLayout::new(&[
-				wrath::BufferElement::Vector3
-			]),
+			&[
+				CubeVertex { position: [1.0, 1.0, 0.0] },
+				CubeVertex { position: [0.0, 1.0, 0.0] },
+				CubeVertex { position: [0.0, 0.0, 0.0] },
+				CubeVertex { position: [1.0, 0.0, 0.0] },
+			],
 			&wrath::Indices::U8(vec![
 				0, 1, 2,
 				2, 3, 0,
 			]),
-		);
+		).expect("Could not create cube mesh");
+		self.cube_instances = renderer.create_instance_buffer_from_slice::<CubeInstance>(
+			&[],
+			wrath::BufferUsage::Stream,
+		).expect("Could not create cube instances");
 		self.flat_cube_shader = renderer.create_shader(
 			Path::new("sandbox/assets/shaders/cube.glsl")
-		);
+		).unwrap_or_else(|e| panic!("{}", e));
 
 		renderer.set_uniform(
 			self.flat_cube_shader,
@@ -146,37 +179,59 @@ impl wrath::Overlay for SnakeOverlay {
 			ROWS.into(),
 		);
 
+		self.font = Some(wrath::Font::bitmap(renderer).expect("Could not create font"));
+
 		renderer.set_clear_color((0.0, 0.0, 1.0).into());
 	}
-	fn on_render(&mut self, renderer: &mut dyn wrath::Renderer) {
-		for x in 0..COLS {
-			renderer.set_uniform(
-				self.flat_cube_shader,
-				"u_x",
-				x.into(),
+	fn on_detach(&mut self, renderer: &mut dyn wrath::Renderer) {
+		renderer.delete_instance_buffer(self.cube_instances)
+			.expect("Could not delete cube instances");
+		renderer.delete_mesh(self.cube_mesh)
+			.expect("Could not delete cube mesh");
+		renderer.delete_shader(self.flat_cube_shader)
+			.expect("Could not delete cube shader");
+		if let Some(font) = self.font.take() {
+			font.delete(renderer).expect("Could not delete font");
+		}
+	}
+	fn on_render(&mut self, renderer: &mut dyn wrath::Renderer, _alpha: whm::Float) {
+		let cell = |(x, y): (u32, u32)| [x as whm::Float, y as whm::Float];
+		let mut instances = self.snake.iter()
+			.map(|&pos| CubeInstance { cell: cell(pos), color: SNAKE_COLOR })
+			.collect::<Vec<_>>();
+		instances.push(CubeInstance { cell: cell(self.fruit), color: FRUIT_COLOR });
+
+		renderer.update_instance_buffer_from_slice(self.cube_instances, 0, &instances)
+			.expect("Could not update cube instances");
+		renderer.render_instanced(
+			self.cube_mesh,
+			self.flat_cube_shader,
+			self.cube_instances,
+			instances.len(),
+		);
+	}
+	fn on_render_2d(&mut self, renderer: &mut wrath::Renderer2D) {
+		renderer.set_view(Some(wrath::Rect::new(0.0, 0.0, COLS as _, ROWS as _)));
+
+		// the board grid goes over the cubes
+		for x in 1..COLS {
+			let x = x as whm::Float;
+			renderer.draw_line((x, 0.0), (x, ROWS as _), 0.05, GRID_COLOR);
+		}
+		for y in 1..ROWS {
+			let y = y as whm::Float;
+			renderer.draw_line((0.0, y), (COLS as _, y), 0.05, GRID_COLOR);
+		}
+
+		if let Some(font) = &mut self.font {
+			renderer.set_view(None);
+			renderer.draw_text(
+				font,
+				&format!("Score: {}", self.snake.len() - 1),
+				(10.0, 10.0),
+				&wrath::TextOptions::new(20.0),
+				SCORE_COLOR,
 			);
-			for y in 0..ROWS {
-				renderer.set_uniform(
-					self.flat_cube_shader,
-					"u_y",
-					y.into(),
-				);
-				if self.snake.contains(&(x, y)) {
-					renderer.set_uniform(
-						self.flat_cube_shader,
-						"u_color",
-						SNAKE_COLOR.into(),
-					);
-					renderer.render(self.cube_mesh, self.flat_cube_shader);
-				} else if (x, y) == self.fruit {
-					renderer.set_uniform(
-						self.flat_cube_shader,
-						"u_color",
-						FRUIT_COLOR.into()
-					);
-					renderer.render(self.cube_mesh, self.flat_cube_shader);
-				}
-			}
 		}
 	}
 }
@@ -0,0 +1,122 @@
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, Index, Meta, NestedMeta};
+
+#[proc_macro_derive(Vertex)]
+pub fn derive_vertex(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	match vertex(input) {
+		Ok(tokens) => tokens.into(),
+		Err(error) => error.to_compile_error().into(),
+	}
+}
+
+#[proc_macro_derive(UniformBlock)]
+pub fn derive_uniform_block(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	match uniform_block(input) {
+		Ok(tokens) => tokens.into(),
+		Err(error) => error.to_compile_error().into(),
+	}
+}
+
+fn vertex(input: DeriveInput) -> Result<proc_macro2::TokenStream, Error> {
+	let name = &input.ident;
+	let fields = repr_c_fields(&input, "Vertex")?;
+
+	let types = fields.iter().map(|field| &field.ty).collect::<Vec<_>>();
+
+	// the fields are tightly packed exactly when their sizes add up to the
+	// size of the struct, anything else fails to compile with a size mismatch
+	Ok(quote! {
+		unsafe impl ::wrath::Vertex for #name {
+			fn layout() -> ::wrath::BufferLayout {
+				::wrath::BufferLayout::new(&[
+					#(<#types as ::wrath::VertexAttribute>::ELEMENT,)*
+				])
+			}
+		}
+
+		const _: [(); 0 #(+ <#types as ::wrath::VertexAttribute>::SIZE)*] =
+			[(); ::std::mem::size_of::<#name>()];
+	})
+}
+
+fn uniform_block(input: DeriveInput) -> Result<proc_macro2::TokenStream, Error> {
+	let name = &input.ident;
+	let fields = repr_c_fields(&input, "UniformBlock")?;
+
+	let types = fields.iter().map(|field| &field.ty).collect::<Vec<_>>();
+	let members = fields
+		.iter()
+		.enumerate()
+		.map(|(i, field)| match &field.ident {
+			Some(ident) => quote!(#ident),
+			None => {
+				let index = Index::from(i);
+				quote!(#index)
+			}
+		})
+		.collect::<Vec<_>>();
+
+	// repr(C) only pads fields to their own alignment, so the offsets are
+	// measured through a pointer to uninitialized memory, without ever making a
+	// value, and compared with the std140 ones at runtime
+	Ok(quote! {
+		unsafe impl ::wrath::UniformBlock for #name {
+			fn layout() -> ::wrath::UniformBlockLayout {
+				::wrath::UniformBlockLayout::new(&[
+					#(<#types as ::wrath::UniformBlockField>::ELEMENT,)*
+				])
+			}
+			fn offsets() -> Option<Vec<usize>> {
+				let value = ::std::mem::MaybeUninit::<#name>::uninit();
+				let base = value.as_ptr();
+				Some(vec![
+					#(unsafe { ::std::ptr::addr_of!((*base).#members) } as usize - base as usize,)*
+				])
+			}
+		}
+	})
+}
+
+fn repr_c_fields<'a>(input: &'a DeriveInput, trait_name: &str) -> Result<&'a Fields, Error> {
+	if !is_repr_c(input) {
+		return Err(Error::new(
+			Span::call_site(),
+			format!("{} can only be derived for #[repr(C)] structs", trait_name),
+		));
+	}
+	let fields = match &input.data {
+		Data::Struct(data) => &data.fields,
+		_ => {
+			return Err(Error::new(
+				Span::call_site(),
+				format!("{} can only be derived for structs", trait_name),
+			))
+		}
+	};
+	if !input.generics.params.is_empty() {
+		return Err(Error::new(
+			Span::call_site(),
+			format!("{} can not be derived for generic structs", trait_name),
+		));
+	}
+	Ok(fields)
+}
+
+fn is_repr_c(input: &DeriveInput) -> bool {
+	input.attrs.iter().any(|attr| match attr.parse_meta() {
+		Ok(Meta::List(list)) => {
+			list.path.is_ident("repr")
+				&& list.nested.iter().any(|nested| match nested {
+					NestedMeta::Meta(meta) => meta.path().is_ident("C"),
+					_ => false,
+				})
+		}
+		_ => false,
+	})
+}
@@ -0,0 +1,94 @@
+use std::{error::Error, fmt};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowBackend {
+	Glutin,
+	Glfw,
+	X11,
+	Headless,
+}
+
+// the first one of glutin, glfw and x11 that is compiled in
+impl Default for WindowBackend {
+	fn default() -> Self {
+		if cfg!(feature = "glutin") {
+			WindowBackend::Glutin
+		} else if cfg!(feature = "glfw") {
+			WindowBackend::Glfw
+		} else if cfg!(feature = "x11") {
+			WindowBackend::X11
+		} else {
+			WindowBackend::Headless
+		}
+	}
+}
+
+impl fmt::Display for WindowBackend {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			f,
+			"{}",
+			match self {
+				WindowBackend::Glutin => "glutin",
+				WindowBackend::Glfw => "glfw",
+				WindowBackend::X11 => "x11",
+				WindowBackend::Headless => "headless",
+			}
+		)
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RendererBackend {
+	OpenGL,
+	Recording,
+}
+
+impl Default for RendererBackend {
+	fn default() -> Self {
+		RendererBackend::OpenGL
+	}
+}
+
+impl fmt::Display for RendererBackend {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			f,
+			"{}",
+			match self {
+				RendererBackend::OpenGL => "OpenGL",
+				RendererBackend::Recording => "recording",
+			}
+		)
+	}
+}
+
+#[derive(Debug)]
+pub enum BackendError {
+	WindowBackendNotCompiled(WindowBackend),
+	NoGraphicsContext(WindowBackend, RendererBackend),
+	// with what the backend failed at
+	WindowCreation(WindowBackend, String),
+}
+
+impl fmt::Display for BackendError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			BackendError::WindowBackendNotCompiled(backend) => write!(
+				f,
+				"The {} window backend was requested but wrath was compiled without the \"{}\" feature",
+				backend, backend,
+			),
+			BackendError::NoGraphicsContext(window, renderer) => write!(
+				f,
+				"The {} window backend does not provide a graphics context for the {} renderer",
+				window, renderer,
+			),
+			BackendError::WindowCreation(backend, message) => {
+				write!(f, "Could not create the {} window: {}", backend, message)
+			}
+		}
+	}
+}
+
+impl Error for BackendError {}
@@ -0,0 +1,274 @@
+use crate::input::get_mouse_position;
+
+use whm::{Float, Matrix4, Vector3};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+	// fov is vertical and in radians
+	Perspective {
+		fov: Float,
+		near: Float,
+		far: Float,
+	},
+	// height is how many world units fit vertically on screen
+	Orthographic {
+		height: Float,
+		near: Float,
+		far: Float,
+	},
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray {
+	pub origin: Vector3,
+	// normalized
+	pub direction: Vector3,
+}
+
+// looks down -z when yaw and pitch are 0, y is up
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+	pub position: Vector3,
+	// radians, yaw turns right around y and pitch up around the right axis
+	pub yaw: Float,
+	pub pitch: Float,
+	pub projection: Projection,
+	viewport_size: (u32, u32),
+}
+
+impl Camera {
+	pub fn perspective(fov: Float, near: Float, far: Float) -> Self {
+		Self::new(Projection::Perspective { fov, near, far })
+	}
+	pub fn orthographic(height: Float, near: Float, far: Float) -> Self {
+		Self::new(Projection::Orthographic { height, near, far })
+	}
+	pub fn new(projection: Projection) -> Self {
+		Self {
+			position: (0.0, 0.0, 0.0).into(),
+			yaw: 0.0,
+			pitch: 0.0,
+			projection,
+			viewport_size: (1, 1),
+		}
+	}
+	pub fn is_3d(&self) -> bool {
+		match self.projection {
+			Projection::Perspective { .. } => true,
+			Projection::Orthographic { .. } => false,
+		}
+	}
+	// the engine calls this when the window is resized
+	pub fn set_viewport_size(&mut self, size: (u32, u32)) {
+		self.viewport_size = (size.0.max(1), size.1.max(1));
+	}
+	pub fn viewport_size(&self) -> (u32, u32) {
+		self.viewport_size
+	}
+	pub fn aspect(&self) -> Float {
+		self.viewport_size.0 as Float / self.viewport_size.1 as Float
+	}
+	pub fn look_at(&mut self, target: Vector3) {
+		let direction = normalize(target - self.position);
+		self.yaw = direction[0].atan2(-direction[2]);
+		self.pitch = direction[1].asin();
+	}
+	pub fn forward(&self) -> Vector3 {
+		self.axes().2
+	}
+	pub fn right(&self) -> Vector3 {
+		self.axes().0
+	}
+	pub fn up(&self) -> Vector3 {
+		self.axes().1
+	}
+	pub fn view_matrix(&self) -> Matrix4 {
+		let (r, u, f) = self.axes();
+		let eye = self.position;
+		Matrix4::new(
+			r[0], u[0], -f[0], 0.0,
+			r[1], u[1], -f[1], 0.0,
+			r[2], u[2], -f[2], 0.0,
+			-dot(r, eye), -dot(u, eye), dot(f, eye), 1.0,
+		)
+	}
+	pub fn projection_matrix(&self) -> Matrix4 {
+		match self.projection {
+			Projection::Perspective { fov, near, far } => {
+				let f = 1.0 / (fov / 2.0).tan();
+				Matrix4::new(
+					f / self.aspect(), 0.0, 0.0, 0.0,
+					0.0, f, 0.0, 0.0,
+					0.0, 0.0, (far + near) / (near - far), -1.0,
+					0.0, 0.0, 2.0 * far * near / (near - far), 0.0,
+				)
+			}
+			Projection::Orthographic { height, near, far } => {
+				let half_height = height / 2.0;
+				let half_width = half_height * self.aspect();
+				Matrix4::new(
+					1.0 / half_width, 0.0, 0.0, 0.0,
+					0.0, 1.0 / half_height, 0.0, 0.0,
+					0.0, 0.0, -2.0 / (far - near), 0.0,
+					0.0, 0.0, -(far + near) / (far - near), 1.0,
+				)
+			}
+		}
+	}
+	pub fn view_projection(&self) -> Matrix4 {
+		self.projection_matrix() * self.view_matrix()
+	}
+	// position is in pixels from the top left of the viewport
+	pub fn screen_to_ray(&self, position: (u32, u32)) -> Ray {
+		let x = 2.0 * position.0 as Float / self.viewport_size.0 as Float - 1.0;
+		let y = 1.0 - 2.0 * position.1 as Float / self.viewport_size.1 as Float;
+		let (right, up, forward) = self.axes();
+
+		match self.projection {
+			Projection::Perspective { fov, .. } => {
+				let half_height = (fov / 2.0).tan();
+				let half_width = half_height * self.aspect();
+				Ray {
+					origin: self.position,
+					direction: normalize(
+						forward + right * (x * half_width) + up * (y * half_height),
+					),
+				}
+			}
+			Projection::Orthographic { height, .. } => {
+				let half_height = height / 2.0;
+				let half_width = half_height * self.aspect();
+				Ray {
+					origin: self.position + right * (x * half_width) + up * (y * half_height),
+					direction: forward,
+				}
+			}
+		}
+	}
+	pub fn mouse_ray(&self) -> Ray {
+		self.screen_to_ray(get_mouse_position())
+	}
+	fn axes(&self) -> (Vector3, Vector3, Vector3) {
+		let (yaw_sin, yaw_cos) = self.yaw.sin_cos();
+		let (pitch_sin, pitch_cos) = self.pitch.sin_cos();
+		let forward = Vector3::new(pitch_cos * yaw_sin, pitch_sin, -pitch_cos * yaw_cos);
+		let right = Vector3::new(yaw_cos, 0.0, yaw_sin);
+		let up = cross(right, forward);
+		(right, up, forward)
+	}
+}
+
+// what the engine uploads to the uniform block named "Camera" every frame:
+//
+// layout(std140) uniform Camera {
+// 	mat4 u_view;
+// 	mat4 u_projection;
+// 	mat4 u_view_projection;
+// 	vec3 u_camera_position;
+// };
+#[derive(Clone, Copy, crate::UniformBlock)]
+#[repr(C)]
+pub(crate) struct CameraBlock {
+	view: [[Float; 4]; 4],
+	projection: [[Float; 4]; 4],
+	view_projection: [[Float; 4]; 4],
+	position: [Float; 3],
+}
+
+impl CameraBlock {
+	pub fn new(camera: &Camera) -> Self {
+		let view = camera.view_matrix();
+		let projection = camera.projection_matrix();
+		Self {
+			view: columns(&view),
+			projection: columns(&projection),
+			view_projection: columns(&(projection * view)),
+			position: [camera.position[0], camera.position[1], camera.position[2]],
+		}
+	}
+}
+
+// the block is uploaded as bytes, so the floats are copied out column by column
+fn columns(matrix: &Matrix4) -> [[Float; 4]; 4] {
+	let mut columns = [[0.0; 4]; 4];
+	for (i, column) in columns.iter_mut().enumerate() {
+		for (j, value) in column.iter_mut().enumerate() {
+			*value = matrix[i][j];
+		}
+	}
+	columns
+}
+
+fn dot(a: Vector3, b: Vector3) -> Float {
+	a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: Vector3, b: Vector3) -> Vector3 {
+	Vector3::new(
+		a[1] * b[2] - a[2] * b[1],
+		a[2] * b[0] - a[0] * b[2],
+		a[0] * b[1] - a[1] * b[0],
+	)
+}
+
+fn normalize(a: Vector3) -> Vector3 {
+	a * (1.0 / dot(a, a).sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn camera_rays() {
+		let mut camera = Camera::orthographic(10.0, 0.1, 100.0);
+		camera.position = (1.0, 2.0, 3.0).into();
+		camera.set_viewport_size((80, 60));
+		assert!(!camera.is_3d());
+
+		let ray = camera.screen_to_ray((40, 30));
+		assert_eq!(ray.origin, camera.position);
+		assert_eq!(ray.direction, camera.forward());
+
+		camera.projection = Projection::Perspective {
+			fov: 1.0,
+			near: 0.1,
+			far: 100.0,
+		};
+		assert!(camera.is_3d());
+		let ray = camera.screen_to_ray((0, 0));
+		assert_eq!(ray.origin, camera.position);
+		assert_ne!(ray.direction, camera.forward());
+	}
+
+	#[test]
+	fn camera_matrices() {
+		let mut camera = Camera::orthographic(2.0, 1.0, 3.0);
+		let identity = [
+			[1.0, 0.0, 0.0, 0.0],
+			[0.0, 1.0, 0.0, 0.0],
+			[0.0, 0.0, 1.0, 0.0],
+			[0.0, 0.0, 0.0, 1.0],
+		];
+		assert_eq!(columns(&camera.view_matrix()), identity);
+
+		// the view moves the world opposite to the camera
+		camera.position = Vector3::new(1.0, 2.0, 3.0);
+		assert_eq!(columns(&camera.view_matrix())[3], [-1.0, -2.0, -3.0, 1.0]);
+		assert_eq!(
+			columns(&camera.projection_matrix()),
+			[
+				[1.0, 0.0, 0.0, 0.0],
+				[0.0, 1.0, 0.0, 0.0],
+				[0.0, 0.0, -1.0, 0.0],
+				[0.0, 0.0, -2.0, 1.0],
+			]
+		);
+
+		let block = CameraBlock::new(&camera);
+		assert_eq!(block.view, columns(&camera.view_matrix()));
+		assert_eq!(block.view_projection, columns(&camera.view_projection()));
+		assert_eq!(block.view_projection[3], [-1.0, -2.0, 1.0, 1.0]);
+		assert_eq!(block.position, [1.0, 2.0, 3.0]);
+	}
+}
@@ -0,0 +1,630 @@
+use crate::{Button, Font, Overlay, Rect, Renderer, Renderer2D, TextOptions};
+
+use whm::Float;
+
+use std::{cell::RefCell, collections::HashMap, ops::RangeInclusive, rc::Rc};
+
+const WINDOW_WIDTH: Float = 260.0;
+const TITLE_HEIGHT: Float = 20.0;
+const PADDING: Float = 6.0;
+const ROW_HEIGHT: Float = 18.0;
+const PLOT_HEIGHT: Float = 48.0;
+// the bitmap font is crisp at multiples of 5
+const TEXT_SIZE: Float = 10.0;
+
+const WINDOW_COLOR: [Float; 4] = [0.1, 0.1, 0.12, 0.85];
+const TITLE_COLOR: [Float; 4] = [0.2, 0.25, 0.4, 0.95];
+const WIDGET_COLOR: [Float; 4] = [0.25, 0.25, 0.3, 1.0];
+const HOVERED_COLOR: [Float; 4] = [0.35, 0.35, 0.45, 1.0];
+const ACTIVE_COLOR: [Float; 4] = [0.45, 0.5, 0.7, 1.0];
+const TEXT_COLOR: [Float; 4] = [0.9, 0.9, 0.9, 1.0];
+
+enum Shape {
+	Quad(Rect, [Float; 4]),
+	Line((Float, Float), (Float, Float), [Float; 4]),
+	Text(String, (Float, Float), [Float; 4]),
+}
+
+struct WindowState {
+	position: (Float, Float),
+	// as of the last frame it was drawn, zero when it wasn't
+	size: (Float, Float),
+}
+
+struct UiState {
+	font: Option<Font>,
+	mouse: (Float, Float),
+	mouse_delta: (Float, Float),
+	mouse_held: bool,
+	// edges since the last frame, a press is taken by the first widget under it
+	pressed: bool,
+	released: bool,
+	typed: String,
+	keys: Vec<Button>,
+	// buttons whose press went to the ui, so their release does too
+	captured: Vec<Button>,
+	// the widget being clicked or dragged
+	active: Option<String>,
+	// the text field being typed into
+	focused: Option<String>,
+	windows: HashMap<String, WindowState>,
+	// back to front
+	order: Vec<String>,
+	shapes: HashMap<String, Vec<Shape>>,
+}
+
+impl UiState {
+	fn window_at(&self, point: (Float, Float)) -> Option<&str> {
+		self.order
+			.iter()
+			.rev()
+			.find(|title| {
+				let window = &self.windows[*title];
+				Rect::new(
+					window.position.0,
+					window.position.1,
+					window.size.0,
+					window.size.1,
+				)
+				.contains(point)
+			})
+			.map(|title| title.as_str())
+	}
+	fn is_hovered(&self) -> bool {
+		self.window_at(self.mouse).is_some()
+	}
+	fn text_width(&self, text: &str) -> Float {
+		match &self.font {
+			Some(font) => font.measure(text, &TextOptions::new(TEXT_SIZE)).0,
+			None => 0.0,
+		}
+	}
+	fn draw(&mut self, renderer: &mut Renderer2D) {
+		let font = match &mut self.font {
+			Some(font) => font,
+			None => return,
+		};
+		for title in &self.order {
+			for shape in self.shapes.get(title).into_iter().flatten() {
+				match shape {
+					Shape::Quad(rect, color) => {
+						renderer.draw_quad((rect.x, rect.y), (rect.width, rect.height), *color)
+					}
+					Shape::Line(from, to, color) => renderer.draw_line(*from, *to, 1.0, *color),
+					Shape::Text(text, position, color) => renderer.draw_text(
+						font,
+						text,
+						*position,
+						&TextOptions::new(TEXT_SIZE),
+						*color,
+					),
+				}
+			}
+		}
+	}
+	fn end_frame(&mut self) {
+		for (title, window) in &mut self.windows {
+			if !self.shapes.contains_key(title) {
+				window.size = (0.0, 0.0);
+			}
+		}
+		if self.released {
+			self.active = None;
+		}
+		self.pressed = false;
+		self.released = false;
+		self.mouse_delta = (0.0, 0.0);
+		self.typed.clear();
+		self.keys.clear();
+		self.shapes.clear();
+	}
+}
+
+// a handle to the ui of a DebugUiOverlay, call its widgets from on_update of
+// any overlay and they show up at the end of the frame
+#[derive(Clone)]
+pub struct DebugUi(Rc<RefCell<UiState>>);
+
+impl DebugUi {
+	// windows are identified by their title and can't be nested
+	pub fn window(&self, title: &str, build: impl FnOnce(&mut DebugWindow)) {
+		let mut state = self.0.borrow_mut();
+		let state = &mut *state;
+
+		if !state.windows.contains_key(title) {
+			let offset = 20.0 + 30.0 * state.windows.len() as Float;
+			state.windows.insert(
+				title.into(),
+				WindowState {
+					position: (offset, offset),
+					size: (0.0, 0.0),
+				},
+			);
+			state.order.push(title.into());
+		}
+
+		let hovered = state.window_at(state.mouse) == Some(title);
+		// clicked windows come to the front
+		if hovered && state.pressed {
+			state.order.retain(|t| t != title);
+			state.order.push(title.into());
+		}
+
+		let mut window = DebugWindow {
+			state: &mut *state,
+			title,
+			hovered,
+			position: (0.0, 0.0),
+			cursor: TITLE_HEIGHT + PADDING,
+			shapes: vec![],
+		};
+		window.title_bar();
+		build(&mut window);
+
+		let DebugWindow {
+			position,
+			cursor,
+			mut shapes,
+			..
+		} = window;
+		let size = (WINDOW_WIDTH, cursor);
+		shapes.insert(
+			0,
+			Shape::Quad(
+				Rect::new(position.0, position.1, size.0, size.1),
+				WINDOW_COLOR,
+			),
+		);
+		state.windows.get_mut(title).unwrap().size = size;
+		state.shapes.insert(title.into(), shapes);
+	}
+	pub fn is_hovered(&self) -> bool {
+		self.0.borrow().is_hovered()
+	}
+}
+
+// lays out widgets from the top down, every widget returns whether the user
+// changed its value this frame
+pub struct DebugWindow<'a> {
+	state: &'a mut UiState,
+	title: &'a str,
+	hovered: bool,
+	position: (Float, Float),
+	cursor: Float,
+	shapes: Vec<Shape>,
+}
+
+impl<'a> DebugWindow<'a> {
+	pub fn label(&mut self, text: &str) {
+		let rect = self.row(ROW_HEIGHT);
+		self.text(text, (rect.x, rect.y), TEXT_COLOR);
+	}
+	// true when clicked
+	pub fn button(&mut self, label: &str) -> bool {
+		let rect = self.row(ROW_HEIGHT);
+		let (hovered, active, _) = self.interact(label, rect);
+		self.shapes
+			.push(Shape::Quad(rect, widget_color(hovered, active)));
+		let x = rect.x + (rect.width - self.state.text_width(label)) / 2.0;
+		self.text(label, (x, rect.y), TEXT_COLOR);
+
+		active && hovered && self.state.released
+	}
+	pub fn checkbox(&mut self, label: &str, value: &mut bool) -> bool {
+		let rect = self.row(ROW_HEIGHT);
+		let (hovered, active, pressed) = self.interact(label, rect);
+		if pressed {
+			*value = !*value;
+		}
+
+		let inset = (ROW_HEIGHT - 12.0) / 2.0;
+		let check = Rect::new(rect.x, rect.y + inset, 12.0, 12.0);
+		self.shapes
+			.push(Shape::Quad(check, widget_color(hovered, active)));
+		if *value {
+			let mark = Rect::new(check.x + 3.0, check.y + 3.0, 6.0, 6.0);
+			self.shapes.push(Shape::Quad(mark, TEXT_COLOR));
+		}
+		self.text(label, (rect.x + 18.0, rect.y), TEXT_COLOR);
+
+		pressed
+	}
+	pub fn slider(&mut self, label: &str, value: &mut Float, range: RangeInclusive<Float>) -> bool {
+		let (label_rect, rect) = self.labeled_row();
+		let (hovered, active, _) = self.interact(label, rect);
+		let (start, end) = (*range.start(), *range.end());
+
+		let mut changed = false;
+		if active && (self.state.mouse_held || self.state.released) {
+			let t = ((self.state.mouse.0 - rect.x) / rect.width)
+				.max(0.0)
+				.min(1.0);
+			let new = start + t * (end - start);
+			changed = new != *value;
+			*value = new;
+		}
+
+		let t = ((*value - start) / (end - start)).max(0.0).min(1.0);
+		self.shapes.push(Shape::Quad(rect, WIDGET_COLOR));
+		let fill = Rect::new(rect.x, rect.y, rect.width * t, rect.height);
+		self.shapes
+			.push(Shape::Quad(fill, widget_color(hovered, active)));
+		let text = format!("{:.2}", value);
+		let x = rect.x + (rect.width - self.state.text_width(&text)) / 2.0;
+		self.text(&text, (x, rect.y), TEXT_COLOR);
+		self.text(label, (label_rect.x, label_rect.y), TEXT_COLOR);
+
+		changed
+	}
+	// focused by clicking it, enter or escape let go of the keyboard again
+	pub fn text_field(&mut self, label: &str, value: &mut String) -> bool {
+		let (label_rect, rect) = self.labeled_row();
+		let id = self.id(label);
+		let (hovered, _, pressed) = self.interact(label, rect);
+		if pressed {
+			self.state.focused = Some(id.clone());
+		}
+		let focused = self.state.focused.as_ref() == Some(&id);
+
+		let mut changed = false;
+		if focused {
+			for c in self.state.typed.chars().filter(|c| !c.is_control()) {
+				value.push(c);
+				changed = true;
+			}
+			for key in &self.state.keys {
+				match key {
+					Button::Backspace => changed |= value.pop().is_some(),
+					Button::Enter | Button::NumPadEnter | Button::Escape => {
+						self.state.focused = None
+					}
+					_ => {}
+				}
+			}
+		}
+
+		self.shapes
+			.push(Shape::Quad(rect, widget_color(hovered, focused)));
+		let text = if focused {
+			format!("{}_", value)
+		} else {
+			value.clone()
+		};
+		self.text(&text, (rect.x + 3.0, rect.y), TEXT_COLOR);
+		self.text(label, (label_rect.x, label_rect.y), TEXT_COLOR);
+
+		changed
+	}
+	// a line through the values, scaled to fit between their min and max
+	pub fn plot(&mut self, label: &str, values: &[Float]) {
+		match values.last() {
+			Some(last) => self.label(&format!("{}: {:.2}", label, last)),
+			None => self.label(label),
+		}
+
+		let rect = self.row(PLOT_HEIGHT);
+		self.shapes.push(Shape::Quad(rect, WIDGET_COLOR));
+		if values.len() < 2 {
+			return;
+		}
+
+		let min = values.iter().copied().fold(Float::INFINITY, Float::min);
+		let max = values.iter().copied().fold(Float::NEG_INFINITY, Float::max);
+		let range = if max > min { max - min } else { 1.0 };
+		let point = |i: usize, value: Float| {
+			(
+				rect.x + i as Float / (values.len() - 1) as Float * rect.width,
+				rect.y + rect.height - (value - min) / range * rect.height,
+			)
+		};
+		for i in 1..values.len() {
+			self.shapes.push(Shape::Line(
+				point(i - 1, values[i - 1]),
+				point(i, values[i]),
+				ACTIVE_COLOR,
+			));
+		}
+	}
+	fn title_bar(&mut self) {
+		let window = &self.state.windows[self.title];
+		let bar = Rect::new(
+			window.position.0,
+			window.position.1,
+			WINDOW_WIDTH,
+			TITLE_HEIGHT,
+		);
+		let (_, active, _) = self.interact("#title", bar);
+		if active && self.state.mouse_held {
+			let delta = self.state.mouse_delta;
+			let window = self.state.windows.get_mut(self.title).unwrap();
+			window.position.0 += delta.0;
+			window.position.1 += delta.1;
+		}
+
+		let position = self.state.windows[self.title].position;
+		self.position = position;
+		self.shapes.push(Shape::Quad(
+			Rect::new(position.0, position.1, WINDOW_WIDTH, TITLE_HEIGHT),
+			TITLE_COLOR,
+		));
+		let title = self.title;
+		self.text(title, (position.0 + PADDING, position.1), TEXT_COLOR);
+	}
+	fn id(&self, label: &str) -> String {
+		format!("{}/{}", self.title, label)
+	}
+	// whether the widget is hovered, being held and was pressed this frame
+	fn interact(&mut self, label: &str, rect: Rect) -> (bool, bool, bool) {
+		let id = self.id(label);
+		let hovered = self.hovered && rect.contains(self.state.mouse);
+		let pressed = hovered && self.state.pressed;
+		if pressed {
+			self.state.pressed = false;
+			self.state.active = Some(id.clone());
+		}
+		let active = self.state.active.as_ref() == Some(&id);
+		(hovered, active, pressed)
+	}
+	fn row(&mut self, height: Float) -> Rect {
+		let rect = Rect::new(
+			self.position.0 + PADDING,
+			self.position.1 + self.cursor,
+			WINDOW_WIDTH - 2.0 * PADDING,
+			height,
+		);
+		self.cursor += height + PADDING;
+		rect
+	}
+	// the label takes the left 40% of the row
+	fn labeled_row(&mut self) -> (Rect, Rect) {
+		let rect = self.row(ROW_HEIGHT);
+		let label_width = rect.width * 0.4;
+		(
+			Rect::new(rect.x, rect.y, label_width, rect.height),
+			Rect::new(
+				rect.x + label_width,
+				rect.y,
+				rect.width - label_width,
+				rect.height,
+			),
+		)
+	}
+	// vertically centered in a row
+	fn text(&mut self, text: &str, position: (Float, Float), color: [Float; 4]) {
+		let y = position.1 + (ROW_HEIGHT - TEXT_SIZE) / 2.0;
+		self.shapes
+			.push(Shape::Text(text.into(), (position.0, y), color));
+	}
+}
+
+fn widget_color(hovered: bool, active: bool) -> [Float; 4] {
+	if active {
+		ACTIVE_COLOR
+	} else if hovered {
+		HOVERED_COLOR
+	} else {
+		WIDGET_COLOR
+	}
+}
+
+// push it to the front of the overlay stack so the ui gets input first and is
+// drawn on top, events it handles don't reach the overlays behind it
+pub struct DebugUiOverlay {
+	ui: DebugUi,
+}
+
+impl DebugUiOverlay {
+	pub fn new() -> Self {
+		Self {
+			ui: DebugUi(Rc::new(RefCell::new(UiState {
+				font: None,
+				mouse: (0.0, 0.0),
+				mouse_delta: (0.0, 0.0),
+				mouse_held: false,
+				pressed: false,
+				released: false,
+				typed: String::new(),
+				keys: vec![],
+				captured: vec![],
+				active: None,
+				focused: None,
+				windows: HashMap::new(),
+				order: vec![],
+				shapes: HashMap::new(),
+			}))),
+		}
+	}
+	pub fn ui(&self) -> DebugUi {
+		self.ui.clone()
+	}
+}
+
+impl Overlay for DebugUiOverlay {
+	fn on_attach(&mut self, renderer: &mut dyn Renderer) {
+		// without a font the ui still works, it just has no text
+		match Font::bitmap(renderer) {
+			Ok(font) => self.ui.0.borrow_mut().font = Some(font),
+			Err(error) => renderer.report_error(&error),
+		}
+	}
+	fn on_detach(&mut self, renderer: &mut dyn Renderer) {
+		if let Some(font) = self.ui.0.borrow_mut().font.take() {
+			if let Err(error) = font.delete(renderer) {
+				renderer.report_error(&error);
+			}
+		}
+	}
+	fn on_render_2d(&mut self, renderer: &mut Renderer2D) {
+		let mut state = self.ui.0.borrow_mut();
+		state.draw(renderer);
+		state.end_frame();
+	}
+	// moves always go through, the input state has to know where the mouse is
+	fn on_mouse_move(&mut self, position: (u32, u32), delta: (i32, i32)) -> bool {
+		let mut state = self.ui.0.borrow_mut();
+		state.mouse = (position.0 as Float, position.1 as Float);
+		state.mouse_delta.0 += delta.0 as Float;
+		state.mouse_delta.1 += delta.1 as Float;
+		false
+	}
+	fn on_mouse_down(&mut self, button: Button) -> bool {
+		let mut state = self.ui.0.borrow_mut();
+		if button == Button::MouseLeft {
+			state.focused = None;
+		}
+		if !state.is_hovered() {
+			return false;
+		}
+		if button == Button::MouseLeft {
+			state.pressed = true;
+			state.mouse_held = true;
+		}
+		state.captured.push(button);
+		true
+	}
+	fn on_mouse_up(&mut self, button: Button) -> bool {
+		let mut state = self.ui.0.borrow_mut();
+		if !state.captured.contains(&button) {
+			return false;
+		}
+		state.captured.retain(|b| *b != button);
+		if button == Button::MouseLeft {
+			state.released = true;
+			state.mouse_held = false;
+		}
+		true
+	}
+	fn on_mouse_scroll(&mut self, _delta: (Float, Float)) -> bool {
+		self.ui.0.borrow().is_hovered()
+	}
+	fn on_key_press(&mut self, button: Button, _repeat: bool) -> bool {
+		let mut state = self.ui.0.borrow_mut();
+		if state.focused.is_none() {
+			return false;
+		}
+		state.keys.push(button);
+		if !state.captured.contains(&button) {
+			state.captured.push(button);
+		}
+		true
+	}
+	fn on_key_release(&mut self, button: Button) -> bool {
+		let mut state = self.ui.0.borrow_mut();
+		if !state.captured.contains(&button) {
+			return false;
+		}
+		state.captured.retain(|b| *b != button);
+		true
+	}
+	fn on_text_written(&mut self, which: char) -> bool {
+		let mut state = self.ui.0.borrow_mut();
+		if state.focused.is_none() {
+			return false;
+		}
+		state.typed.push(which);
+		true
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		events::{MouseDownEvent, MouseMoveEvent, MouseUpEvent},
+		BufferElement, BufferLayout, BufferUsage, Indices, MeshHandle, OverlayStack,
+		RecordingRenderer, RenderCommand, ShaderHandle, Vertices,
+	};
+
+	use std::path::Path;
+
+	struct SceneOverlay {
+		mesh: MeshHandle,
+		shader: ShaderHandle,
+		clicks: Rc<RefCell<Vec<Button>>>,
+	}
+
+	impl Overlay for SceneOverlay {
+		fn on_render(&mut self, renderer: &mut dyn Renderer, _alpha: Float) {
+			renderer.render(self.mesh, self.shader);
+		}
+		fn on_mouse_down(&mut self, button: Button) -> bool {
+			self.clicks.borrow_mut().push(button);
+			true
+		}
+		fn on_mouse_up(&mut self, button: Button) -> bool {
+			self.clicks.borrow_mut().push(button);
+			true
+		}
+	}
+
+	#[test]
+	fn debug_ui_overlay() {
+		let renderer = RecordingRenderer::new();
+		let log = renderer.log();
+		let mut renderer: Box<dyn Renderer> = box renderer;
+		let mesh = renderer
+			.create_mesh_from_layout(
+				&Vertices::new(vec![0.0; 9]),
+				&BufferLayout::new(&[BufferElement::Vector3]),
+				&Indices::U8(vec![0, 1, 2]),
+				BufferUsage::Static,
+			)
+			.unwrap();
+		let shader = renderer.create_shader(Path::new("scene.glsl")).unwrap();
+		let clicks = Rc::new(RefCell::new(Vec::new()));
+
+		let mut stack = OverlayStack::new();
+		stack.push_back(
+			box SceneOverlay {
+				mesh,
+				shader,
+				clicks: clicks.clone(),
+			},
+			renderer.as_mut(),
+		);
+		let overlay = DebugUiOverlay::new();
+		let ui = overlay.ui();
+		let handle = stack.push_front(box overlay, renderer.as_mut());
+		let mut renderer_2d = Renderer2D::new();
+		renderer_2d.set_viewport_size((800, 600));
+
+		let mut frame = |stack: &mut OverlayStack, renderer: &mut dyn Renderer| {
+			let mut clicked = false;
+			ui.window("Test", |window| clicked = window.button("Press"));
+			stack.call_render(renderer, &mut renderer_2d, 0.0);
+			clicked
+		};
+
+		// the window only takes input once it has been laid out
+		assert!(!frame(&mut stack, renderer.as_mut()));
+		log.take_commands();
+		stack.submit(MouseMoveEvent::boxed((100, 50), (0, 0)));
+		stack.submit(MouseDownEvent::boxed(Button::MouseLeft));
+		assert!(!frame(&mut stack, renderer.as_mut()));
+		stack.submit(MouseUpEvent::boxed(Button::MouseLeft));
+		assert!(frame(&mut stack, renderer.as_mut()));
+		assert!(clicks.borrow().is_empty());
+
+		// the ui is drawn over the overlay behind it
+		let shaders = log
+			.take_commands()
+			.into_iter()
+			.filter_map(|c| match c {
+				RenderCommand::Render { shader, .. } => Some(shader),
+				_ => None,
+			})
+			.collect::<Vec<_>>();
+		assert_eq!(shaders.first(), Some(&shader));
+		assert_ne!(shaders.last(), Some(&shader));
+
+		// clicks outside the ui go through
+		stack.submit(MouseMoveEvent::boxed((500, 500), (400, 450)));
+		stack.submit(MouseDownEvent::boxed(Button::MouseLeft));
+		assert_eq!(*clicks.borrow(), vec![Button::MouseLeft]);
+
+		assert!(stack.remove_overlay(handle, renderer.as_mut()));
+		renderer_2d.delete(renderer.as_mut()).unwrap();
+		assert!(log.live_textures().is_empty());
+		assert!(log.take_errors().is_empty());
+	}
+}
@@ -1,24 +1,54 @@
 use crate::{
-	input::INPUT_STATE, window, Button, CallbackHandler, Overlay, OverlayHandle, OverlayStack,
-	Renderer, Window, WindowProps,
+	camera::{Camera, CameraBlock},
+	events::EventType,
+	input::INPUT_STATE,
+	rendering, window, BackendError, Button, CallbackHandler, Overlay, OverlayHandle, OverlayStack,
+	RenderState, Renderer, Renderer2D, RendererBackend, UniformBufferHandle, Window, WindowBackend,
+	WindowProps,
 };
 
-use std::time::Instant;
+use whm::Float;
+
+use std::time::{Duration, Instant};
+
+const FIXED_TIMESTEP: Duration = Duration::from_nanos(1_000_000_000 / 60);
+const MAX_FIXED_STEPS: u32 = 8;
 
 pub struct Engine {
 	window: Box<dyn Window>,
 	is_running: bool,
 	overlay_stack: OverlayStack,
 	last_update: Instant,
+	fixed_timestep: FixedTimestep,
 	renderer: Box<dyn Renderer>,
+	renderer_2d: Renderer2D,
+	camera: Option<Camera>,
+	camera_buffer: UniformBufferHandle,
 }
 
 impl Engine {
-	pub fn new(props: EngineProps) -> Self {
-		let window = window::create(props.window_props);
+	pub fn new(props: EngineProps) -> Result<Self, BackendError> {
+		// the headless window has no gl context for the renderer to load
+		if props.renderer_backend == RendererBackend::OpenGL
+			&& props.window_backend == WindowBackend::Headless
+		{
+			return Err(BackendError::NoGraphicsContext(
+				props.window_backend,
+				props.renderer_backend,
+			));
+		}
+		let window = window::create(props.window_backend, props.window_props)?;
+		let renderer = rendering::create(props.renderer_backend);
 
-		let mut renderer = box crate::platform::opengl_renderer::OpenGLRenderer::new();
+		let mut engine = Self::from_parts(window, renderer);
+		engine.set_fixed_timestep(props.fixed_timestep, props.max_fixed_steps);
+		Ok(engine)
+	}
+	pub fn from_parts(window: Box<dyn Window>, mut renderer: Box<dyn Renderer>) -> Self {
 		renderer.set_clear_color((0.0, 0.06, 0.12).into());
+		renderer.set_viewport((0, 0), window.get_size());
+		let mut renderer_2d = Renderer2D::new();
+		renderer_2d.set_viewport_size(window.get_size());
 
 		let mut overlay_stack = OverlayStack::new();
 		overlay_stack.push_back(box InputPollingUpdateOverlay, renderer.as_mut());
@@ -28,7 +58,11 @@ impl Engine {
 			is_running: true,
 			overlay_stack,
 			last_update: Instant::now(),
+			fixed_timestep: FixedTimestep::new(FIXED_TIMESTEP, MAX_FIXED_STEPS),
 			renderer,
+			renderer_2d,
+			camera: None,
+			camera_buffer: UniformBufferHandle::none(),
 		}
 	}
 	pub fn update(&mut self, handler: &mut dyn CallbackHandler) {
@@ -40,15 +74,33 @@ impl Engine {
 			if self.window.close_requested() {
 				self.is_running = false;
 			}
+			if event.event_type() == EventType::WindowResized {
+				let size = self.window.get_size();
+				self.renderer.set_viewport((0, 0), size);
+				self.renderer_2d.set_viewport_size(size);
+				if let Some(camera) = &mut self.camera {
+					camera.set_viewport_size(size);
+				}
+			}
 			self.overlay_stack.submit(event);
 		}
 
 		handler.on_update(self);
 
 		self.overlay_stack.call_update(dt);
+		for _ in 0..self.fixed_timestep.advance(dt) {
+			self.overlay_stack
+				.call_fixed_update(self.fixed_timestep.step);
+		}
 
+		self.renderer.poll_shader_changes();
+		self.prepare_camera();
 		self.renderer.clear();
-		self.overlay_stack.call_render(self.renderer.as_mut());
+		self.overlay_stack.call_render(
+			self.renderer.as_mut(),
+			&mut self.renderer_2d,
+			self.fixed_timestep.alpha(),
+		);
 		self.window.swap_buffers();
 	}
 	pub fn is_running(&self) -> bool {
@@ -75,10 +127,145 @@ impl Engine {
 	pub fn renderer(&mut self) -> &mut dyn Renderer {
 		self.renderer.as_mut()
 	}
+	// at most max_steps fixed updates run per frame, time beyond that is dropped
+	// so a slow frame can't make the next one slower
+	pub fn set_fixed_timestep(&mut self, step: Duration, max_steps: u32) {
+		self.fixed_timestep = FixedTimestep::new(step, max_steps);
+	}
+	pub fn fixed_timestep(&self) -> Duration {
+		self.fixed_timestep.step
+	}
+	// the camera follows the window size and is uploaded to the uniform block
+	// "Camera" before every frame
+	pub fn set_camera(&mut self, camera: Option<Camera>) {
+		let size = self.window.get_size();
+		self.camera = camera.map(|mut camera| {
+			camera.set_viewport_size(size);
+			camera
+		});
+	}
+	pub fn camera(&self) -> Option<&Camera> {
+		self.camera.as_ref()
+	}
+	pub fn camera_mut(&mut self) -> Option<&mut Camera> {
+		self.camera.as_mut()
+	}
+	// frames start out writing depth and depth tested only with a 3d camera, the
+	// rest of the render state is left as the overlays set it
+	fn prepare_camera(&mut self) {
+		let state = RenderState {
+			depth_test: self.camera.as_ref().map_or(false, Camera::is_3d),
+			depth_write: true,
+			..*self.renderer.render_state()
+		};
+		self.renderer.set_render_state(&state);
+
+		let camera = match &self.camera {
+			Some(camera) => camera,
+			None => return,
+		};
+		let block = CameraBlock::new(camera);
+		let result = if self.camera_buffer == UniformBufferHandle::none() {
+			self.renderer
+				.create_uniform_block("Camera", &block)
+				.map(|handle| self.camera_buffer = handle)
+		} else {
+			self.renderer
+				.update_uniform_block(self.camera_buffer, &block)
+		};
+		if let Err(error) = result {
+			self.renderer.report_error(&error);
+		}
+	}
+}
+
+// runs before the fields are dropped, so the renderer is still there to free
+// the engine's own resources with
+impl Drop for Engine {
+	fn drop(&mut self) {
+		let mut result = self.renderer_2d.delete(self.renderer.as_mut());
+		if self.camera_buffer != UniformBufferHandle::none() {
+			result = result.and(self.renderer.delete_uniform_buffer(self.camera_buffer));
+			self.camera_buffer = UniformBufferHandle::none();
+		}
+		if let Err(error) = result {
+			self.renderer.report_error(&error);
+		}
+	}
 }
 
 pub struct EngineProps {
 	pub window_props: WindowProps,
+	pub window_backend: WindowBackend,
+	pub renderer_backend: RendererBackend,
+	// the time simulated by every on_fixed_update
+	pub fixed_timestep: Duration,
+	pub max_fixed_steps: u32,
+}
+
+impl EngineProps {
+	// the default backends that are compiled in and 60 fixed updates a second
+	pub fn new(title: &str, size: (u32, u32)) -> Self {
+		let window_backend = WindowBackend::default();
+		Self {
+			window_props: WindowProps {
+				title: title.into(),
+				size,
+			},
+			window_backend,
+			renderer_backend: match window_backend {
+				WindowBackend::Headless => RendererBackend::Recording,
+				_ => RendererBackend::default(),
+			},
+			fixed_timestep: FIXED_TIMESTEP,
+			max_fixed_steps: MAX_FIXED_STEPS,
+		}
+	}
+}
+
+impl Default for EngineProps {
+	fn default() -> Self {
+		Self::new("wrath", (1280, 720))
+	}
+}
+
+pub(crate) struct FixedTimestep {
+	pub step: Duration,
+	max_steps: u32,
+	accumulator: Duration,
+}
+
+impl FixedTimestep {
+	pub fn new(step: Duration, max_steps: u32) -> Self {
+		assert!(
+			step > Duration::new(0, 0),
+			"The fixed timestep can't be zero"
+		);
+		Self {
+			step,
+			max_steps,
+			accumulator: Duration::new(0, 0),
+		}
+	}
+	// returns how many steps to run for a frame that took dt
+	pub fn advance(&mut self, dt: Duration) -> u32 {
+		self.accumulator += dt;
+		let mut steps = 0;
+		while self.accumulator >= self.step && steps < self.max_steps {
+			self.accumulator -= self.step;
+			steps += 1;
+		}
+		if self.accumulator >= self.step {
+			let behind = self.accumulator.as_nanos() % self.step.as_nanos();
+			self.accumulator = Duration::from_nanos(behind as u64);
+		}
+		steps
+	}
+	// how far the simulation is into the next step, to interpolate between the
+	// last two states with
+	pub fn alpha(&self) -> Float {
+		self.accumulator.as_secs_f32() / self.step.as_secs_f32()
+	}
 }
 
 struct InputPollingUpdateOverlay;
@@ -335,3 +522,159 @@ impl Overlay for InputPollingUpdateOverlay {
 		false
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		BlendMode, BufferElement, BufferLayout, BufferUsage, CullMode, Indices, MeshHandle,
+		RecordingRenderer, RenderCommand, ShaderHandle, ShaderUniform, Vertices,
+	};
+
+	use std::{cell::Cell, path::Path, rc::Rc};
+
+	struct TestHandler;
+
+	impl CallbackHandler for TestHandler {
+		fn on_create(&mut self, _engine: &mut Engine) {}
+		fn on_update(&mut self, _engine: &mut Engine) {}
+		fn on_exit(&mut self, _engine: &mut Engine) {}
+	}
+
+	struct TestOverlay {
+		shader: ShaderHandle,
+		mesh: MeshHandle,
+		presses: Rc<Cell<u32>>,
+	}
+
+	impl Overlay for TestOverlay {
+		fn on_attach(&mut self, renderer: &mut dyn Renderer) {
+			self.shader = renderer.create_shader(Path::new("test.glsl")).unwrap();
+			self.mesh = renderer
+				.create_mesh_from_layout(
+					&Vertices::new(vec![0.0, 0.0, 0.0]),
+					&BufferLayout::new(&[BufferElement::Vector3]),
+					&Indices::U8(vec![0]),
+					BufferUsage::Static,
+				)
+				.unwrap();
+		}
+		fn on_detach(&mut self, renderer: &mut dyn Renderer) {
+			renderer.delete_mesh(self.mesh).unwrap();
+			renderer.delete_shader(self.shader).unwrap();
+		}
+		fn on_render(&mut self, renderer: &mut dyn Renderer, _alpha: whm::Float) {
+			renderer.set_uniform(self.shader, "u_value", 1.0.into());
+			renderer.render(self.mesh, self.shader);
+		}
+		fn on_key_press(&mut self, _button: Button, _repeat: bool) -> bool {
+			self.presses.set(self.presses.get() + 1);
+			true
+		}
+	}
+
+	#[test]
+	fn fixed_timestep() {
+		let mut timestep = FixedTimestep::new(Duration::from_millis(10), 3);
+		assert_eq!(timestep.advance(Duration::from_millis(5)), 0);
+		assert!((timestep.alpha() - 0.5).abs() < 1e-6);
+		assert_eq!(timestep.advance(Duration::from_millis(25)), 3);
+		// the rest of a slow frame is dropped instead of being caught up on
+		assert_eq!(timestep.advance(Duration::from_millis(100)), 3);
+		assert_eq!(timestep.advance(Duration::from_millis(5)), 0);
+		assert!((timestep.alpha() - 0.5).abs() < 1e-6);
+	}
+
+	#[cfg(feature = "headless")]
+	#[test]
+	fn headless_engine() {
+		use crate::{events, HeadlessWindow};
+
+		let window = HeadlessWindow::new(WindowProps {
+			title: "test".into(),
+			size: (80, 60),
+		});
+		let script = window.script();
+		let renderer = RecordingRenderer::new();
+		let log = renderer.log();
+
+		let mut engine = Engine::from_parts(box window, box renderer);
+		let presses = Rc::new(Cell::new(0));
+		let overlay = engine.push_overlay_front(box TestOverlay {
+			shader: ShaderHandle::none(),
+			mesh: MeshHandle::none(),
+			presses: presses.clone(),
+		});
+
+		script.push_event(events::KeyPressedEvent::boxed(Button::A, false));
+		engine.update(&mut TestHandler);
+		assert_eq!(presses.get(), 1);
+		assert_eq!(script.frames(), 1);
+
+		let commands = log.take_commands();
+		assert!(commands.contains(&RenderCommand::SetViewport {
+			position: (0, 0),
+			size: (80, 60),
+		}));
+		let render = commands
+			.iter()
+			.position(|c| match c {
+				RenderCommand::Render { .. } => true,
+				_ => false,
+			})
+			.expect("overlay did not render");
+		match &commands[render - 1] {
+			RenderCommand::SetUniform { name, value, .. } => {
+				assert_eq!(name, "u_value");
+				assert_eq!(*value, ShaderUniform::Float(1.0));
+			}
+			_ => panic!("expected uniform before render"),
+		}
+
+		// the camera only touches the depth state the overlays left behind
+		engine.set_camera(Some(Camera::perspective(1.0, 0.1, 100.0)));
+		engine.renderer().set_render_state(&RenderState {
+			depth_write: false,
+			cull: CullMode::Back,
+			blend: BlendMode::None,
+			..Default::default()
+		});
+		engine.update(&mut TestHandler);
+		assert_eq!(
+			*engine.renderer().render_state(),
+			RenderState {
+				depth_test: true,
+				cull: CullMode::Back,
+				blend: BlendMode::None,
+				..Default::default()
+			}
+		);
+		assert!(log.take_errors().is_empty());
+
+		engine.set_camera(None);
+		engine.update(&mut TestHandler);
+		assert!(!engine.renderer().render_state().depth_test);
+
+		script.push_event(events::WindowCloseRequestedEvent::boxed());
+		engine.update(&mut TestHandler);
+		assert!(!engine.is_running());
+
+		assert!(engine.remove_overlay(overlay));
+		assert!(log.live_shaders().is_empty());
+		assert!(log.live_meshes().is_empty());
+
+		// the engine frees the 2d renderer and the camera block it created
+		engine
+			.renderer_2d
+			.draw_quad((0.0, 0.0), (1.0, 1.0), [1.0; 4]);
+		engine.renderer_2d.flush(engine.renderer.as_mut()).unwrap();
+		assert!(!log.live_textures().is_empty());
+		assert_eq!(log.live_uniform_buffers().len(), 1);
+		drop(engine);
+		assert!(log.live_shaders().is_empty());
+		assert!(log.live_meshes().is_empty());
+		assert!(log.live_textures().is_empty());
+		assert!(log.live_uniform_buffers().is_empty());
+		assert!(log.take_errors().is_empty());
+	}
+}
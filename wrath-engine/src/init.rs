@@ -1,9 +1,13 @@
+use crate::BackendError;
 use crate::CallbackHandler;
 use crate::Engine;
 use crate::EngineProps;
 
-pub fn init<T: CallbackHandler>(mut handler: T, engine_props: EngineProps) {
-	let mut engine = Engine::new(engine_props);
+pub fn init<T: CallbackHandler>(
+	mut handler: T,
+	engine_props: EngineProps,
+) -> Result<(), BackendError> {
+	let mut engine = Engine::new(engine_props)?;
 	handler.on_create(&mut engine);
 
 	while engine.is_running() {
@@ -11,4 +15,6 @@ pub fn init<T: CallbackHandler>(mut handler: T, engine_props: EngineProps) {
 	}
 
 	handler.on_exit(&mut engine);
+
+	Ok(())
 }
@@ -1,28 +1,56 @@
 #![feature(box_syntax)]
 
+// lets the derive macros refer to ::wrath from inside the crate
+extern crate self as wrath;
+
+mod backend;
 mod callback_handler;
+mod debug_ui;
 mod engine;
-mod events;
 mod init;
 mod overlay;
 mod platform;
 mod rendering;
 mod window;
 
+pub mod camera;
+pub mod events;
 pub mod input;
 
+pub use backend::{BackendError, RendererBackend, WindowBackend};
 pub use callback_handler::CallbackHandler;
+pub use camera::{Camera, Projection, Ray};
+pub use debug_ui::{DebugUi, DebugUiOverlay, DebugWindow};
 pub use engine::{Engine, EngineProps};
 pub use events::Event;
 pub use init::init;
 pub use input::{Button, InputState};
 pub use overlay::{Overlay, OverlayHandle, OverlayStack};
+#[cfg(feature = "headless")]
+pub use platform::headless_window::{HeadlessScript, HeadlessWindow};
 pub use rendering::{
-	mesh::{BufferElement, BufferLayout, Indices, MeshHandle, Vertices},
+	error::RendererError,
+	font::{Font, TextAlign, TextOptions},
+	framebuffer::{DepthFormat, FramebufferHandle, FramebufferSpec},
+	mesh::{
+		BufferElement, BufferLayout, BufferUsage, DrawParams, DrawRange, Indices,
+		InstanceBufferHandle, MeshHandle, Primitive, Vertices,
+	},
+	recording_renderer::{RecordingRenderer, RenderCommand, RenderLog},
+	reflection::{AttributeInfo, ShaderReflection, UniformBlockInfo, UniformInfo, UniformType},
+	render_state::{BlendMode, CompareFunc, CullMode, RenderState, StencilOp, StencilState},
+	renderer_2d::{Rect, Renderer2D},
 	shader::{ShaderHandle, ShaderType, ShaderUniform},
+	texture::{SamplerState, TextureFilter, TextureFormat, TextureHandle, TextureWrap},
+	uniform_buffer::{
+		UniformBlock, UniformBlockElement, UniformBlockField, UniformBlockLayout,
+		UniformBufferHandle,
+	},
+	vertex::{Normalized, Vertex, VertexAttribute},
 	Renderer,
 };
 pub use window::{Window, WindowProps};
+pub use wrath_derive::{UniformBlock, Vertex};
 
 #[cfg(test)]
 mod tests {
@@ -1,4 +1,4 @@
-use crate::{Button, Renderer};
+use crate::{Button, Renderer, Renderer2D};
 
 use whm::Float;
 
@@ -15,7 +15,13 @@ pub trait Overlay {
 	fn on_attach(&mut self, renderer: &mut dyn Renderer) {}
 	fn on_detach(&mut self, renderer: &mut dyn Renderer) {}
 	fn on_update(&mut self, dt: Duration) {}
-	fn on_render(&mut self, renderer: &mut dyn Renderer) {}
+	// runs zero or more times per frame with the same step every time
+	fn on_fixed_update(&mut self, step: Duration) {}
+	// alpha is how far between the last and the next fixed update the frame is
+	fn on_render(&mut self, renderer: &mut dyn Renderer, alpha: Float) {}
+	// called after on_render, everything drawn here ends up on top of what this
+	// overlay and the ones behind it rendered
+	fn on_render_2d(&mut self, renderer: &mut Renderer2D) {}
 	fn on_window_close_requested(&mut self) {}
 	fn on_window_resize(&mut self, size: (u32, u32)) {}
 	fn on_key_press(&mut self, button: Button, repeat: bool) -> bool {
@@ -1,5 +1,7 @@
 use super::{Overlay, OverlayHandle};
-use crate::{Event, Renderer};
+use crate::{Event, Renderer, Renderer2D};
+
+use whm::Float;
 
 use std::{collections::VecDeque, time::Duration};
 
@@ -28,9 +30,27 @@ impl OverlayStack {
 			overlay.0.on_update(dt);
 		}
 	}
-	pub fn call_render(&mut self, renderer: &mut dyn Renderer) {
+	pub fn call_fixed_update(&mut self, step: Duration) {
 		for overlay in self.inner.iter_mut() {
-			overlay.0.on_render(renderer);
+			overlay.0.on_fixed_update(step);
+		}
+	}
+	pub fn call_render(
+		&mut self,
+		renderer: &mut dyn Renderer,
+		renderer_2d: &mut Renderer2D,
+		alpha: Float,
+	) {
+		// back to front, so the overlays that get events first are drawn on top
+		for overlay in self.inner.iter_mut().rev() {
+			overlay.0.on_render(renderer, alpha);
+			// every overlay starts out drawing in pixels
+			renderer_2d.set_view(None);
+			overlay.0.on_render_2d(renderer_2d);
+			// so the next overlay's 3d draws end up on top of this one's 2d
+			if let Err(error) = renderer_2d.flush(renderer) {
+				renderer.report_error(&error);
+			}
 		}
 	}
 	pub fn push_back(
@@ -69,3 +89,82 @@ impl OverlayStack {
 		false
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		BufferElement, BufferLayout, BufferUsage, Indices, MeshHandle, RecordingRenderer,
+		RenderCommand, ShaderHandle, Vertices,
+	};
+
+	use std::path::Path;
+
+	struct LayeredOverlay {
+		mesh: MeshHandle,
+		shader: ShaderHandle,
+	}
+
+	impl Overlay for LayeredOverlay {
+		fn on_render(&mut self, renderer: &mut dyn Renderer, _alpha: Float) {
+			renderer.render(self.mesh, self.shader);
+		}
+		fn on_render_2d(&mut self, renderer: &mut Renderer2D) {
+			renderer.draw_quad((0.0, 0.0), (10.0, 10.0), [1.0; 4]);
+		}
+	}
+
+	#[test]
+	fn overlays_flush_in_order() {
+		let renderer = RecordingRenderer::new();
+		let log = renderer.log();
+		let mut renderer: Box<dyn Renderer> = box renderer;
+		let mesh = renderer
+			.create_mesh_from_layout(
+				&Vertices::new(vec![0.0; 9]),
+				&BufferLayout::new(&[BufferElement::Vector3]),
+				&Indices::U8(vec![0, 1, 2]),
+				BufferUsage::Static,
+			)
+			.unwrap();
+		let first = renderer.create_shader(Path::new("first.glsl")).unwrap();
+		let second = renderer.create_shader(Path::new("second.glsl")).unwrap();
+
+		let mut stack = OverlayStack::new();
+		stack.push_back(
+			box LayeredOverlay {
+				mesh,
+				shader: first,
+			},
+			renderer.as_mut(),
+		);
+		stack.push_back(
+			box LayeredOverlay {
+				mesh,
+				shader: second,
+			},
+			renderer.as_mut(),
+		);
+		let mut renderer_2d = Renderer2D::new();
+		renderer_2d.set_viewport_size((80, 60));
+		log.take_commands();
+
+		stack.call_render(renderer.as_mut(), &mut renderer_2d, 0.0);
+		let shaders = log
+			.take_commands()
+			.into_iter()
+			.filter_map(|c| match c {
+				RenderCommand::Render { shader, .. } => Some(shader),
+				_ => None,
+			})
+			.collect::<Vec<_>>();
+		// the front overlay renders last and each overlay's 2d batch is drawn
+		// before the next overlay renders
+		assert_eq!(shaders.len(), 4);
+		assert_eq!(shaders[0], second);
+		assert_eq!(shaders[2], first);
+		assert_eq!(shaders[1], shaders[3]);
+		assert!(![first, second].contains(&shaders[1]));
+		assert!(log.take_errors().is_empty());
+	}
+}
@@ -1,4 +1,9 @@
+#[cfg(feature = "glfw")]
+pub mod glfw_window;
+#[cfg(feature = "glutin")]
 pub mod glutin_window;
+#[cfg(feature = "headless")]
+pub mod headless_window;
 pub mod opengl_renderer;
-// pub mod x11_window;
-// pub mod glfw_window;
+#[cfg(feature = "x11")]
+pub mod x11_window;
@@ -2,7 +2,7 @@ use crate::{events::*, input::get_mouse_position, Button, Event, Window, WindowP
 
 use std::sync::Once;
 
-use wrath_math::Float;
+use whm::Float;
 
 type GlfwEventLoop = std::sync::mpsc::Receiver<(f64, glfw::WindowEvent)>;
 static mut GLFW_HANDLE: Option<glfw::Glfw> = None;
@@ -0,0 +1,97 @@
+use crate::{
+	events::{EventType, WindowResizedEvent},
+	Event, Window, WindowProps,
+};
+
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+pub struct HeadlessWindow {
+	title: String,
+	state: Rc<RefCell<HeadlessState>>,
+	close_requested: bool,
+}
+
+struct HeadlessState {
+	size: (u32, u32),
+	events: VecDeque<Box<dyn Event>>,
+	frames: u64,
+	on_swap: Option<Box<dyn FnMut(u64)>>,
+}
+
+impl HeadlessWindow {
+	pub fn new(props: WindowProps) -> Self {
+		Self {
+			title: props.title,
+			state: Rc::new(RefCell::new(HeadlessState {
+				size: props.size,
+				events: VecDeque::new(),
+				frames: 0,
+				on_swap: None,
+			})),
+			close_requested: false,
+		}
+	}
+	pub fn script(&self) -> HeadlessScript {
+		HeadlessScript(self.state.clone())
+	}
+}
+
+impl Window for HeadlessWindow {
+	fn set_title(&mut self, title: String) {
+		self.title = title;
+	}
+	fn get_title(&self) -> &str {
+		&self.title
+	}
+	fn get_size(&self) -> (u32, u32) {
+		self.state.borrow().size
+	}
+	fn update(&mut self) -> Vec<Box<dyn Event>> {
+		let events: Vec<Box<dyn Event>> = self.state.borrow_mut().events.drain(..).collect();
+		if events
+			.iter()
+			.any(|e| e.event_type() == EventType::WindowCloseRequested)
+		{
+			self.close_requested = true;
+		}
+		events
+	}
+	fn swap_buffers(&mut self) {
+		let (frame, on_swap) = {
+			let mut state = self.state.borrow_mut();
+			state.frames += 1;
+			(state.frames, state.on_swap.take())
+		};
+		// the callback is taken out while it runs so it may use the script
+		if let Some(mut on_swap) = on_swap {
+			on_swap(frame);
+			let mut state = self.state.borrow_mut();
+			if state.on_swap.is_none() {
+				state.on_swap = Some(on_swap);
+			}
+		}
+	}
+	fn close_requested(&self) -> bool {
+		self.close_requested
+	}
+}
+
+#[derive(Clone)]
+pub struct HeadlessScript(Rc<RefCell<HeadlessState>>);
+
+impl HeadlessScript {
+	pub fn push_event(&self, event: Box<dyn Event>) {
+		self.0.borrow_mut().events.push_back(event);
+	}
+	pub fn resize(&self, size: (u32, u32)) {
+		let mut state = self.0.borrow_mut();
+		state.size = size;
+		state.events.push_back(WindowResizedEvent::boxed(size));
+	}
+	pub fn frames(&self) -> u64 {
+		self.0.borrow().frames
+	}
+	pub fn on_swap(&self, callback: Box<dyn FnMut(u64)>) {
+		self.0.borrow_mut().on_swap = Some(callback);
+	}
+}
@@ -1,13 +1,39 @@
 use crate::{
-	BufferLayout, Indices, MeshHandle, Renderer, ShaderHandle, ShaderType, ShaderUniform, Vertices,
+	rendering::{mesh, preprocessor, texture},
+	AttributeInfo, BlendMode, BufferElement, BufferLayout, BufferUsage, CompareFunc, CullMode,
+	DepthFormat, DrawParams, DrawRange, FramebufferHandle, FramebufferSpec, Indices,
+	InstanceBufferHandle, MeshHandle, Primitive, RenderState, Renderer, RendererError,
+	SamplerState, ShaderHandle, ShaderReflection, ShaderType, ShaderUniform, StencilOp,
+	TextureFilter, TextureFormat, TextureHandle, TextureWrap, UniformBlockInfo, UniformBlockLayout,
+	UniformBufferHandle, UniformInfo, UniformType, Vertices,
 };
 
 use whm::Vector3;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ffi::CString;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+const HOT_RELOAD_INTERVAL: Duration = Duration::from_millis(500);
+
+// GL_EXT_texture_filter_anisotropic, core since 4.6
+const TEXTURE_MAX_ANISOTROPY: u32 = 0x84FE;
+
+const ERROR_VERTEX_SHADER: &str = "#version 330 core
+layout(location = 0) in vec3 in_pos;
+void main() {
+	gl_Position = vec4(in_pos, 1.0);
+}
+";
+
+const ERROR_FRAGMENT_SHADER: &str = "#version 330 core
+layout(location = 0) out vec4 out_color;
+void main() {
+	out_color = vec4(1.0, 0.0, 1.0, 1.0);
+}
+";
 
 pub struct OpenGLRenderer {
 	clear_color: Vector3,
@@ -15,14 +41,30 @@ pub struct OpenGLRenderer {
 	shaders: HashMap<ShaderHandle, Shader>,
 	bound_shader: ShaderHandle,
 	meshes: HashMap<MeshHandle, Mesh>,
+	instance_buffers: HashMap<InstanceBufferHandle, InstanceBuffer>,
 	bound_mesh: MeshHandle,
+	textures: HashMap<TextureHandle, Texture>,
+	// texture bound to each unit
+	bound_textures: HashMap<u32, TextureHandle>,
+	uniform_buffers: HashMap<UniformBufferHandle, UniformBuffer>,
+	framebuffers: HashMap<FramebufferHandle, Framebuffer>,
+	bound_framebuffer: FramebufferHandle,
+	viewport: ((i32, i32), (u32, u32)),
+	// viewport of the default framebuffer, restored when switching back to it
+	default_viewport: ((i32, i32), (u32, u32)),
+	render_state: RenderState,
+	// uniform blocks and buffers meet at the binding point of their name
+	block_bindings: HashMap<String, u32>,
+	shader_fallback: bool,
+	hot_reload: bool,
+	last_poll: Instant,
+	error_callback: Box<dyn FnMut(&RendererError)>,
 }
 
 impl OpenGLRenderer {
 	pub fn new() -> Self {
 		unsafe {
-			gl::Enable(gl::BLEND);
-			gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+			apply_render_state(None, &RenderState::default());
 		}
 		Self {
 			clear_color: (0.0, 0.0, 0.0).into(),
@@ -30,7 +72,44 @@ impl OpenGLRenderer {
 			shaders: Default::default(),
 			bound_shader: ShaderHandle::none(),
 			meshes: Default::default(),
+			instance_buffers: Default::default(),
 			bound_mesh: MeshHandle::none(),
+			textures: Default::default(),
+			bound_textures: Default::default(),
+			uniform_buffers: Default::default(),
+			framebuffers: Default::default(),
+			bound_framebuffer: FramebufferHandle::none(),
+			viewport: ((0, 0), (0, 0)),
+			default_viewport: ((0, 0), (0, 0)),
+			render_state: RenderState::default(),
+			block_bindings: Default::default(),
+			shader_fallback: true,
+			hot_reload: cfg!(debug_assertions),
+			last_poll: Instant::now(),
+			error_callback: box |error: &RendererError| eprintln!("\x1b[31m{}\x1b[0m", error),
+		}
+	}
+	// returns the program together with every file it was built from
+	fn load_shader(
+		path: &Path,
+		defines: &[(String, String)],
+	) -> Result<(u32, Vec<PathBuf>), RendererError> {
+		let source = preprocessor::preprocess(path, defines)?;
+
+		let stages = source
+			.stages
+			.iter()
+			.map(|stage| (stage.type_, stage.source.clone()))
+			.collect::<Vec<_>>();
+
+		match build_shader(&stages) {
+			Ok(id) => Ok((id, source.files)),
+			Err(RendererError::Compile(logs)) => Err(RendererError::Compile(
+				logs.into_iter()
+					.map(|(type_, log)| (type_, source.map_log(type_, &log)))
+					.collect(),
+			)),
+			Err(error) => Err(error),
 		}
 	}
 	fn _delete_shader(&mut self, shader: Shader) {
@@ -40,12 +119,265 @@ impl OpenGLRenderer {
 			});
 		}
 	}
+	fn block_binding(&mut self, name: &str) -> u32 {
+		let next = self.block_bindings.len() as u32;
+		*self.block_bindings.entry(name.into()).or_insert(next)
+	}
+	fn bind_uniform_blocks(&mut self, program: u32, blocks: &[UniformBlockInfo]) {
+		for block in blocks {
+			let binding = self.block_binding(&block.name);
+			unsafe {
+				gl_call("glUniformBlockBinding", || {
+					gl::UniformBlockBinding(program, block.index, binding);
+				});
+			}
+
+			let buffer = self
+				.uniform_buffers
+				.values()
+				.find(|buffer| buffer.name == block.name);
+			if let Some(buffer) = buffer {
+				if buffer.padded_size() < block.size {
+					(self.error_callback)(&RendererError::UniformBlockSizeMismatch {
+						name: block.name.clone(),
+						size: buffer.size,
+						expected: block.size,
+					});
+				}
+			}
+		}
+	}
+	fn _delete_uniform_buffer(&mut self, buffer: UniformBuffer) {
+		unsafe {
+			gl::DeleteBuffers(1, &buffer.id);
+		}
+	}
+	fn bind_shader_textures(&mut self, handle: ShaderHandle) -> Result<(), RendererError> {
+		let textures = self.shaders[&handle]
+			.textures
+			.values()
+			.copied()
+			.collect::<Vec<(u32, TextureHandle)>>();
+		for (unit, texture) in textures {
+			self.bind_texture(texture, unit)?;
+		}
+		Ok(())
+	}
+	fn _delete_texture(&mut self, handle: TextureHandle) {
+		if let Some(texture) = self.textures.remove(&handle) {
+			unsafe {
+				gl::DeleteTextures(1, &texture.id);
+			}
+		}
+		self.bound_textures.retain(|_, bound| *bound != handle);
+	}
+	// returns the framebuffer, the one the textures are attached to if it's
+	// multisampled and its renderbuffers
+	fn build_framebuffer(
+		&self,
+		spec: &FramebufferSpec,
+		textures: &[TextureHandle],
+	) -> Result<(u32, u32, Vec<u32>), RendererError> {
+		let attachments = (0..spec.color.len() as u32)
+			.map(|i| gl::COLOR_ATTACHMENT0 + i)
+			.collect::<Vec<_>>();
+		let mut renderbuffers = vec![];
+		let mut id = 0;
+		let mut resolve_id = 0;
+		unsafe {
+			gl::CreateFramebuffers(1, &mut id);
+			if spec.samples > 1 {
+				gl::CreateFramebuffers(1, &mut resolve_id);
+				for (format, attachment) in spec.color.iter().zip(&attachments) {
+					let (internal_format, _, _) = gl_texture_format(*format);
+					let renderbuffer =
+						create_renderbuffer(internal_format, spec.size, spec.samples);
+					gl::NamedFramebufferRenderbuffer(
+						id,
+						*attachment,
+						gl::RENDERBUFFER,
+						renderbuffer,
+					);
+					renderbuffers.push(renderbuffer);
+				}
+			}
+			let target = if spec.samples > 1 { resolve_id } else { id };
+			for (texture, attachment) in textures.iter().zip(&attachments) {
+				gl_call("glNamedFramebufferTexture", || {
+					gl::NamedFramebufferTexture(target, *attachment, self.textures[texture].id, 0);
+				});
+			}
+			if let Some(depth) = spec.depth {
+				let (internal_format, attachment) = gl_depth_format(depth);
+				let renderbuffer = create_renderbuffer(internal_format, spec.size, spec.samples);
+				gl::NamedFramebufferRenderbuffer(id, attachment, gl::RENDERBUFFER, renderbuffer);
+				renderbuffers.push(renderbuffer);
+			}
+			if attachments.is_empty() {
+				gl::NamedFramebufferDrawBuffer(id, gl::NONE);
+				gl::NamedFramebufferReadBuffer(id, gl::NONE);
+			} else {
+				gl::NamedFramebufferDrawBuffers(id, attachments.len() as i32, attachments.as_ptr());
+			}
+
+			for framebuffer in [id, resolve_id].iter().filter(|id| **id != 0) {
+				let status = gl::CheckNamedFramebufferStatus(*framebuffer, gl::FRAMEBUFFER);
+				if status != gl::FRAMEBUFFER_COMPLETE {
+					delete_framebuffer_objects(id, resolve_id, &renderbuffers);
+					return Err(RendererError::IncompleteFramebuffer(status));
+				}
+			}
+		}
+		Ok((id, resolve_id, renderbuffers))
+	}
+	// copies the multisampled color attachments into the textures
+	fn resolve_framebuffer(&self, handle: FramebufferHandle) {
+		let framebuffer = &self.framebuffers[&handle];
+		if framebuffer.resolve_id == 0 {
+			return;
+		}
+		let (width, height) = (
+			framebuffer.spec.size.0 as i32,
+			framebuffer.spec.size.1 as i32,
+		);
+		for i in 0..framebuffer.spec.color.len() as u32 {
+			unsafe {
+				gl::NamedFramebufferReadBuffer(framebuffer.id, gl::COLOR_ATTACHMENT0 + i);
+				gl::NamedFramebufferDrawBuffer(framebuffer.resolve_id, gl::COLOR_ATTACHMENT0 + i);
+				gl_call("glBlitNamedFramebuffer", || {
+					gl::BlitNamedFramebuffer(
+						framebuffer.id,
+						framebuffer.resolve_id,
+						0,
+						0,
+						width,
+						height,
+						0,
+						0,
+						width,
+						height,
+						gl::COLOR_BUFFER_BIT,
+						gl::NEAREST,
+					);
+				});
+			}
+		}
+	}
+	fn bound_framebuffer_id(&self) -> u32 {
+		self.framebuffers
+			.get(&self.bound_framebuffer)
+			.map_or(0, |framebuffer| framebuffer.id)
+	}
 	fn _delete_mesh(&mut self, mesh: Mesh) {
 		unsafe {
 			gl::DeleteVertexArrays(1, &mesh.va);
-			gl::DeleteBuffers(1, &mesh.vb);
 			gl::DeleteBuffers(1, &mesh.ib);
 		}
+		mesh.vb.delete();
+	}
+	// points the instance attributes of the mesh's vertex array at the buffer,
+	// the mesh has to be bound
+	fn attach_instances(&mut self, mh: MeshHandle, instances: InstanceBufferHandle) {
+		let mesh = self.meshes.get_mut(&mh).unwrap();
+		if mesh.instances == instances {
+			return;
+		}
+		let buffer = &self.instance_buffers[&instances];
+		unsafe {
+			gl::BindBuffer(gl::ARRAY_BUFFER, buffer.buffer.id);
+			let end = enable_attributes(&buffer.layout, mesh.instance_location);
+			gl::BindBuffer(gl::ARRAY_BUFFER, mesh.vb.id);
+			// leftovers of a previous instance buffer with more attributes
+			for location in end..mesh.instance_end {
+				gl::DisableVertexAttribArray(location);
+			}
+			mesh.instance_end = end;
+		}
+		mesh.instances = instances;
+	}
+	fn _render_with(
+		&mut self,
+		mh: MeshHandle,
+		sh: ShaderHandle,
+		params: &DrawParams,
+	) -> Result<(), RendererError> {
+		self.bind_mesh(mh)?;
+		let mesh = &self.meshes[&mh];
+		params.check(mh, mesh.index_count as usize, mesh.vb.count)?;
+		self.bind_shader(sh)?;
+		self.bind_shader_textures(sh)?;
+		let mesh = &self.meshes[&mh];
+		let mode = gl_primitive(params.primitive);
+		unsafe {
+			match params.range {
+				DrawRange::All => gl_call("glDrawElements", || {
+					gl::DrawElements(mode, mesh.index_count, mesh.index_type, std::ptr::null());
+				}),
+				DrawRange::Indices {
+					first,
+					count,
+					base_vertex,
+				} => gl_call("glDrawElementsBaseVertex", || {
+					gl::DrawElementsBaseVertex(
+						mode,
+						count as i32,
+						mesh.index_type,
+						(first * index_size(mesh.index_type)) as *const _,
+						base_vertex,
+					);
+				}),
+				DrawRange::Vertices { first, count } => gl_call("glDrawArrays", || {
+					gl::DrawArrays(mode, first as i32, count as i32);
+				}),
+			}
+		}
+		Ok(())
+	}
+	fn _render_instanced(
+		&mut self,
+		mh: MeshHandle,
+		sh: ShaderHandle,
+		instances: InstanceBufferHandle,
+		count: usize,
+	) -> Result<(), RendererError> {
+		self.bind_mesh(mh)?;
+		let buffer = self
+			.instance_buffers
+			.get(&instances)
+			.ok_or(RendererError::InvalidInstanceBufferHandle(instances))?;
+		mesh::check_instances(instances, buffer.buffer.count, buffer.layout.divisor, count)?;
+		self.attach_instances(mh, instances);
+		self.bind_shader(sh)?;
+		self.bind_shader_textures(sh)?;
+		let mesh = &self.meshes[&mh];
+		unsafe {
+			gl_call("glDrawElementsInstanced", || {
+				gl::DrawElementsInstanced(
+					gl::TRIANGLES,
+					mesh.index_count,
+					mesh.index_type,
+					std::ptr::null(),
+					count as i32,
+				);
+			});
+		}
+		Ok(())
+	}
+	fn _dispatch_compute(
+		&mut self,
+		sh: ShaderHandle,
+		groups: (u32, u32, u32),
+	) -> Result<(), RendererError> {
+		self.bind_shader(sh)?;
+		self.bind_shader_textures(sh)?;
+		unsafe {
+			gl_call("glDispatchCompute", || {
+				gl::DispatchCompute(groups.0, groups.1, groups.2);
+			});
+			// make the writes visible to whatever reads them next
+			gl::MemoryBarrier(gl::ALL_BARRIER_BITS);
+		}
+		Ok(())
 	}
 }
 
@@ -63,6 +395,28 @@ impl Drop for OpenGLRenderer {
 		for mesh in meshes {
 			self._delete_mesh(mesh);
 		}
+		for (_, instances) in self.instance_buffers.drain() {
+			instances.buffer.delete();
+		}
+		let buffers = self
+			.uniform_buffers
+			.drain()
+			.map(|(_, b)| b)
+			.collect::<Vec<UniformBuffer>>();
+		for buffer in buffers {
+			self._delete_uniform_buffer(buffer);
+		}
+		for (_, framebuffer) in self.framebuffers.drain() {
+			delete_framebuffer_objects(
+				framebuffer.id,
+				framebuffer.resolve_id,
+				&framebuffer.renderbuffers,
+			);
+		}
+		let textures = self.textures.keys().copied().collect::<Vec<_>>();
+		for texture in textures {
+			self._delete_texture(texture);
+		}
 	}
 }
 
@@ -73,40 +427,235 @@ impl Renderer for OpenGLRenderer {
 			self.clear_color = color;
 		}
 	}
+	fn set_render_state(&mut self, state: &RenderState) {
+		if *state != self.render_state {
+			unsafe { apply_render_state(Some(&self.render_state), state) };
+			self.render_state = *state;
+		}
+	}
+	fn render_state(&self) -> &RenderState {
+		&self.render_state
+	}
+	fn set_viewport(&mut self, position: (i32, i32), size: (u32, u32)) {
+		if (position, size) != self.viewport {
+			unsafe { gl::Viewport(position.0, position.1, size.0 as i32, size.1 as i32) };
+			self.viewport = (position, size);
+		}
+	}
 	fn clear(&mut self) {
-		unsafe { gl::Clear(gl::COLOR_BUFFER_BIT) };
+		let state = self.render_state;
+		// the write masks apply to clearing too
+		let masked = !state.depth_write
+			|| state.color_mask != [true; 4]
+			|| state
+				.stencil
+				.map_or(false, |stencil| stencil.write_mask != !0);
+		unsafe {
+			if masked {
+				gl::DepthMask(gl::TRUE);
+				gl::ColorMask(gl::TRUE, gl::TRUE, gl::TRUE, gl::TRUE);
+				gl::StencilMask(!0);
+			}
+			gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT | gl::STENCIL_BUFFER_BIT);
+			if masked {
+				apply_write_masks(&state);
+			}
+		}
 	}
-	fn create_shader(&mut self, path: &Path) -> ShaderHandle {
-		let (vertex, fragment) = read_shader_source(path);
-		let vertex = compile_shader(&vertex, ShaderType::Vertex);
-		let fragment = compile_shader(&fragment, ShaderType::Fragment);
+	fn create_shader_with_defines(
+		&mut self,
+		path: &Path,
+		defines: &[(&str, &str)],
+	) -> Result<ShaderHandle, RendererError> {
+		let defines = defines
+			.iter()
+			.map(|(name, value)| (name.to_string(), value.to_string()))
+			.collect::<Vec<_>>();
+
+		let shader = match Self::load_shader(path, &defines) {
+			Ok((id, files)) => Shader::new(id, path.into(), defines, files),
+			Err(error) if self.shader_fallback => {
+				(self.error_callback)(&error);
+				let id = build_shader(&[
+					(ShaderType::Vertex, ERROR_VERTEX_SHADER.into()),
+					(ShaderType::Fragment, ERROR_FRAGMENT_SHADER.into()),
+				])
+				.expect("Could not build the error shader");
+				let files = preprocessor::stage_files(path);
+				let mut shader = Shader::new(id, path.into(), defines, files);
+				shader.fallback = true;
+				shader
+			}
+			Err(error) => return Err(error),
+		};
 
-		let shader = link_shaders(&[vertex, fragment]);
+		self.bind_uniform_blocks(shader.id, &shader.reflection.uniform_blocks);
 
 		let handle = ShaderHandle::new(self.handle_counter);
 		self.handle_counter += 1;
 
 		self.shaders.insert(handle, shader);
 
-		handle
+		Ok(handle)
 	}
-	fn bind_shader(&mut self, handle: ShaderHandle) {
+	fn create_shader_from_source(
+		&mut self,
+		stages: &[(ShaderType, &str)],
+	) -> Result<ShaderHandle, RendererError> {
+		let stages = stages
+			.iter()
+			.map(|(type_, source)| (*type_, source.to_string()))
+			.collect::<Vec<_>>();
+		let shader = Shader::new(build_shader(&stages)?, PathBuf::new(), vec![], vec![]);
+
+		self.bind_uniform_blocks(shader.id, &shader.reflection.uniform_blocks);
+
+		let handle = ShaderHandle::new(self.handle_counter);
+		self.handle_counter += 1;
+
+		self.shaders.insert(handle, shader);
+
+		Ok(handle)
+	}
+	fn reload_shader(&mut self, handle: ShaderHandle) -> Result<(), RendererError> {
+		let (id, files) = match self.shaders.get(&handle) {
+			// built from source, there is nothing to reload
+			Some(shader) if shader.path.as_os_str().is_empty() => return Ok(()),
+			Some(shader) => Self::load_shader(&shader.path, &shader.defines)?,
+			None => return Err(RendererError::InvalidShaderHandle(handle)),
+		};
+
+		let shader = self.shaders.get_mut(&handle).unwrap();
+		let old_id = std::mem::replace(&mut shader.id, id);
+		shader.uniform_cache.clear();
+		shader.reflection = reflect(id);
+		shader.reported.clear();
+		shader.fallback = false;
+		shader.watch(files);
+		let uniforms = shader
+			.uniforms
+			.iter()
+			.map(|(name, value)| (name.clone(), value.clone()))
+			.collect::<Vec<_>>();
+		let blocks = shader.reflection.uniform_blocks.clone();
+		self.bind_uniform_blocks(id, &blocks);
+
+		unsafe {
+			gl_call("glDeleteProgram", || {
+				gl::DeleteProgram(old_id);
+			});
+		}
 		if handle == self.bound_shader {
+			self.bound_shader = ShaderHandle::none();
+		}
+
+		// the new program starts out with default values for every uniform
+		for (name, value) in uniforms {
+			self.set_uniform(handle, &name, value);
+		}
+		Ok(())
+	}
+	fn set_shader_fallback(&mut self, enabled: bool) {
+		self.shader_fallback = enabled;
+	}
+	fn set_shader_hot_reload(&mut self, enabled: bool) {
+		self.hot_reload = enabled;
+	}
+	fn poll_shader_changes(&mut self) {
+		if !self.hot_reload || self.last_poll.elapsed() < HOT_RELOAD_INTERVAL {
 			return;
+		}
+		self.last_poll = Instant::now();
+
+		let changed = self
+			.shaders
+			.iter_mut()
+			.filter_map(|(handle, shader)| {
+				if shader.refresh_sources() {
+					Some(*handle)
+				} else {
+					None
+				}
+			})
+			.collect::<Vec<ShaderHandle>>();
+
+		for handle in changed {
+			// on failure the old program is kept until the source is fixed
+			if let Err(error) = self.reload_shader(handle) {
+				(self.error_callback)(&error);
+			}
+		}
+	}
+	fn set_error_callback(&mut self, callback: Box<dyn FnMut(&RendererError)>) {
+		self.error_callback = callback;
+	}
+	fn report_error(&mut self, error: &RendererError) {
+		(self.error_callback)(error);
+	}
+	fn bind_shader(&mut self, handle: ShaderHandle) -> Result<(), RendererError> {
+		if handle == self.bound_shader {
+			return Ok(());
 		};
-		let shader = &self.shaders[&handle];
+		let shader = self
+			.shaders
+			.get(&handle)
+			.ok_or(RendererError::InvalidShaderHandle(handle))?;
 		unsafe {
 			gl::UseProgram(shader.id);
 		}
 		self.bound_shader = handle;
+		Ok(())
 	}
-	fn delete_shader(&mut self, handle: ShaderHandle) {
-		let shader = self.shaders.remove(&handle).expect("Unknown shader");
+	fn delete_shader(&mut self, handle: ShaderHandle) -> Result<(), RendererError> {
+		let shader = self
+			.shaders
+			.remove(&handle)
+			.ok_or(RendererError::InvalidShaderHandle(handle))?;
+		if handle == self.bound_shader {
+			self.bound_shader = ShaderHandle::none();
+		}
 		self._delete_shader(shader);
+		Ok(())
+	}
+	fn shader_info(&self, handle: ShaderHandle) -> Result<&ShaderReflection, RendererError> {
+		self.shaders
+			.get(&handle)
+			.map(|shader| &shader.reflection)
+			.ok_or(RendererError::InvalidShaderHandle(handle))
 	}
 	fn set_uniform(&mut self, handle: ShaderHandle, name: &str, val: ShaderUniform) {
 		unsafe {
-			let shader = self.shaders.get_mut(&handle).expect("Unknown shader");
+			let shader = match self.shaders.get_mut(&handle) {
+				Some(shader) => shader,
+				None => {
+					(self.error_callback)(&RendererError::InvalidShaderHandle(handle));
+					return;
+				}
+			};
+
+			if cfg!(debug_assertions) && !shader.fallback {
+				let error = match shader.reflection.uniform(name) {
+					None => Some(RendererError::UnknownUniform {
+						handle,
+						name: name.into(),
+					}),
+					Some(info) if !info.type_.accepts(&val) => {
+						Some(RendererError::UniformTypeMismatch {
+							handle,
+							name: name.into(),
+							expected: info.type_,
+						})
+					}
+					Some(_) => None,
+				};
+				// a mismatched upload would be a gl error, so it's skipped
+				if let Some(error) = error {
+					if shader.reported.insert(name.into()) {
+						(self.error_callback)(&error);
+					}
+					return;
+				}
+			}
 
 			if !shader.uniform_cache.contains_key(name) {
 				shader.uniform_cache.insert(
@@ -117,67 +666,691 @@ impl Renderer for OpenGLRenderer {
 				);
 			}
 			let location = shader.uniform_cache[name];
+			shader.uniforms.insert(name.into(), val.clone());
 
-			self.bind_shader(handle);
+			// every texture uniform of a shader keeps the unit it first got
+			let texture_unit = if let ShaderUniform::Texture(texture) = &val {
+				let next = shader.textures.len() as u32;
+				let entry = shader
+					.textures
+					.entry(name.into())
+					.or_insert((next, *texture));
+				entry.1 = *texture;
+				entry.0 as i32
+			} else {
+				0
+			};
+
+			if let Err(error) = self.bind_shader(handle) {
+				(self.error_callback)(&error);
+				return;
+			}
 
-			gl_call("glUniform*", || match val {
-				ShaderUniform::Float(val) => gl::Uniform1f(location, val),
+			gl_call("glUniform*", || match &val {
+				ShaderUniform::Float(val) => gl::Uniform1f(location, *val),
+				ShaderUniform::Vector2(val) => gl::Uniform2f(location, val[0], val[1]),
 				ShaderUniform::Vector3(val) => gl::Uniform3f(location, val[0], val[1], val[2]),
-				ShaderUniform::Vector4(val) => gl::Uniform4f(location, val[0], val[1], val[2], val[3]),
-				ShaderUniform::I32(val) => gl::Uniform1i(location, val),
-				ShaderUniform::U32(val) => gl::Uniform1ui(location, val),
+				ShaderUniform::Vector4(val) => {
+					gl::Uniform4f(location, val[0], val[1], val[2], val[3])
+				}
+				ShaderUniform::I32(val) => gl::Uniform1i(location, *val),
+				ShaderUniform::IVector2(val) => gl::Uniform2iv(location, 1, val.as_ptr()),
+				ShaderUniform::IVector3(val) => gl::Uniform3iv(location, 1, val.as_ptr()),
+				ShaderUniform::IVector4(val) => gl::Uniform4iv(location, 1, val.as_ptr()),
+				ShaderUniform::U32(val) => gl::Uniform1ui(location, *val),
+				ShaderUniform::UVector2(val) => gl::Uniform2uiv(location, 1, val.as_ptr()),
+				ShaderUniform::UVector3(val) => gl::Uniform3uiv(location, 1, val.as_ptr()),
+				ShaderUniform::UVector4(val) => gl::Uniform4uiv(location, 1, val.as_ptr()),
+				ShaderUniform::Bool(val) => gl::Uniform1i(location, *val as i32),
+				ShaderUniform::Matrix2(val, transpose) => gl::UniformMatrix2fv(
+					location,
+					1,
+					*transpose as u8,
+					floats(std::slice::from_ref(val)),
+				),
+				ShaderUniform::Matrix3(val, transpose) => gl::UniformMatrix3fv(
+					location,
+					1,
+					*transpose as u8,
+					floats(std::slice::from_ref(val)),
+				),
+				ShaderUniform::Matrix4(val, transpose) => gl::UniformMatrix4fv(
+					location,
+					1,
+					*transpose as u8,
+					floats(std::slice::from_ref(val)),
+				),
+				ShaderUniform::FloatArray(vals) => {
+					gl::Uniform1fv(location, vals.len() as i32, vals.as_ptr())
+				}
+				ShaderUniform::Vector2Array(vals) => {
+					gl::Uniform2fv(location, vals.len() as i32, floats(vals))
+				}
+				ShaderUniform::Vector3Array(vals) => {
+					gl::Uniform3fv(location, vals.len() as i32, floats(vals))
+				}
+				ShaderUniform::Vector4Array(vals) => {
+					gl::Uniform4fv(location, vals.len() as i32, floats(vals))
+				}
+				ShaderUniform::I32Array(vals) | ShaderUniform::SamplerArray(vals) => {
+					gl::Uniform1iv(location, vals.len() as i32, vals.as_ptr())
+				}
+				ShaderUniform::IVector2Array(vals) => {
+					gl::Uniform2iv(location, vals.len() as i32, vals.as_ptr() as *const i32)
+				}
+				ShaderUniform::IVector3Array(vals) => {
+					gl::Uniform3iv(location, vals.len() as i32, vals.as_ptr() as *const i32)
+				}
+				ShaderUniform::IVector4Array(vals) => {
+					gl::Uniform4iv(location, vals.len() as i32, vals.as_ptr() as *const i32)
+				}
+				ShaderUniform::U32Array(vals) => {
+					gl::Uniform1uiv(location, vals.len() as i32, vals.as_ptr())
+				}
+				ShaderUniform::UVector2Array(vals) => {
+					gl::Uniform2uiv(location, vals.len() as i32, vals.as_ptr() as *const u32)
+				}
+				ShaderUniform::UVector3Array(vals) => {
+					gl::Uniform3uiv(location, vals.len() as i32, vals.as_ptr() as *const u32)
+				}
+				ShaderUniform::UVector4Array(vals) => {
+					gl::Uniform4uiv(location, vals.len() as i32, vals.as_ptr() as *const u32)
+				}
+				ShaderUniform::BoolArray(vals) => {
+					let vals = vals.iter().map(|v| *v as i32).collect::<Vec<i32>>();
+					gl::Uniform1iv(location, vals.len() as i32, vals.as_ptr())
+				}
+				ShaderUniform::Matrix2Array(vals, transpose) => gl::UniformMatrix2fv(
+					location,
+					vals.len() as i32,
+					*transpose as u8,
+					floats(vals),
+				),
+				ShaderUniform::Matrix3Array(vals, transpose) => gl::UniformMatrix3fv(
+					location,
+					vals.len() as i32,
+					*transpose as u8,
+					floats(vals),
+				),
+				ShaderUniform::Matrix4Array(vals, transpose) => gl::UniformMatrix4fv(
+					location,
+					vals.len() as i32,
+					*transpose as u8,
+					floats(vals),
+				),
+				ShaderUniform::Sampler(unit) => gl::Uniform1i(location, *unit),
+				ShaderUniform::Texture(_) => gl::Uniform1i(location, texture_unit),
 			});
 		}
 	}
-	fn create_mesh(
+	fn create_mesh_from_layout(
 		&mut self,
 		vertices: &Vertices,
 		layout: &BufferLayout,
 		indices: &Indices,
-	) -> MeshHandle {
-		let mesh = Mesh::new(vertices, layout, indices);
+		usage: BufferUsage,
+	) -> Result<MeshHandle, RendererError> {
+		if layout.stride == 0 || vertices.size() % layout.stride != 0 {
+			return Err(RendererError::VertexLayoutMismatch {
+				size: vertices.size(),
+				stride: layout.stride,
+			});
+		}
+
+		let mesh = Mesh::new(vertices, layout, indices, usage);
 
 		let handle = MeshHandle::new(self.handle_counter);
 		self.handle_counter += 1;
 		self.meshes.insert(handle, mesh);
 
-		handle
+		Ok(handle)
+	}
+	fn update_vertices(
+		&mut self,
+		handle: MeshHandle,
+		offset: usize,
+		vertices: &Vertices,
+	) -> Result<(), RendererError> {
+		let mesh = self
+			.meshes
+			.get_mut(&handle)
+			.ok_or(RendererError::InvalidMeshHandle(handle))?;
+		if vertices.size() % mesh.vb.stride != 0 {
+			return Err(RendererError::VertexLayoutMismatch {
+				size: vertices.size(),
+				stride: mesh.vb.stride,
+			});
+		}
+		mesh.vb.write(offset, vertices);
+		Ok(())
 	}
-	fn bind_mesh(&mut self, handle: MeshHandle) {
+	fn update_indices(
+		&mut self,
+		handle: MeshHandle,
+		indices: &Indices,
+	) -> Result<(), RendererError> {
+		let mesh = self
+			.meshes
+			.get_mut(&handle)
+			.ok_or(RendererError::InvalidMeshHandle(handle))?;
+		mesh.write_indices(indices);
+		Ok(())
+	}
+	fn bind_mesh(&mut self, handle: MeshHandle) -> Result<(), RendererError> {
 		if handle == self.bound_mesh {
-			return;
+			return Ok(());
 		};
-		let mesh = &self.meshes[&handle];
+		let mesh = self
+			.meshes
+			.get(&handle)
+			.ok_or(RendererError::InvalidMeshHandle(handle))?;
 		unsafe {
 			gl_call("bind mesh", || {
 				gl::BindVertexArray(mesh.va);
-				gl::BindBuffer(gl::ARRAY_BUFFER, mesh.vb);
+				gl::BindBuffer(gl::ARRAY_BUFFER, mesh.vb.id);
 				gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, mesh.ib);
 			});
 		}
 		self.bound_mesh = handle;
+		Ok(())
 	}
-	fn delete_mesh(&mut self, handle: MeshHandle) {
+	fn delete_mesh(&mut self, handle: MeshHandle) -> Result<(), RendererError> {
 		let mesh = self
 			.meshes
 			.remove(&handle)
-			.expect("Tried to delete unknown mesh");
+			.ok_or(RendererError::InvalidMeshHandle(handle))?;
+		if handle == self.bound_mesh {
+			self.bound_mesh = MeshHandle::none();
+		}
 		self._delete_mesh(mesh);
+		Ok(())
 	}
-	fn render(&mut self, mh: MeshHandle, sh: ShaderHandle) {
-		self.bind_mesh(mh);
-		self.bind_shader(sh);
-		let mesh = &self.meshes[&mh];
+	fn create_instance_buffer(
+		&mut self,
+		instances: &Vertices,
+		layout: &BufferLayout,
+		usage: BufferUsage,
+	) -> Result<InstanceBufferHandle, RendererError> {
+		if layout.stride == 0 || instances.size() % layout.stride != 0 {
+			return Err(RendererError::VertexLayoutMismatch {
+				size: instances.size(),
+				stride: layout.stride,
+			});
+		}
+
+		let buffer = InstanceBuffer {
+			buffer: VertexBuffer::new(instances, layout.stride, usage),
+			layout: layout.clone(),
+		};
+
+		let handle = InstanceBufferHandle::new(self.handle_counter);
+		self.handle_counter += 1;
+		self.instance_buffers.insert(handle, buffer);
+
+		Ok(handle)
+	}
+	fn update_instance_buffer(
+		&mut self,
+		handle: InstanceBufferHandle,
+		offset: usize,
+		instances: &Vertices,
+	) -> Result<(), RendererError> {
+		let buffer = &mut self
+			.instance_buffers
+			.get_mut(&handle)
+			.ok_or(RendererError::InvalidInstanceBufferHandle(handle))?
+			.buffer;
+		if instances.size() % buffer.stride != 0 {
+			return Err(RendererError::VertexLayoutMismatch {
+				size: instances.size(),
+				stride: buffer.stride,
+			});
+		}
+		buffer.write(offset, instances);
+		Ok(())
+	}
+	fn delete_instance_buffer(
+		&mut self,
+		handle: InstanceBufferHandle,
+	) -> Result<(), RendererError> {
+		let instances = self
+			.instance_buffers
+			.remove(&handle)
+			.ok_or(RendererError::InvalidInstanceBufferHandle(handle))?;
+		for mesh in self.meshes.values_mut() {
+			if mesh.instances == handle {
+				mesh.instances = InstanceBufferHandle::none();
+			}
+		}
+		instances.buffer.delete();
+		Ok(())
+	}
+	fn create_uniform_buffer(
+		&mut self,
+		name: &str,
+		layout: &UniformBlockLayout,
+	) -> Result<UniformBufferHandle, RendererError> {
+		let binding = self.block_binding(name);
+		let buffer = UniformBuffer {
+			id: 0,
+			name: name.into(),
+			size: layout.size,
+		};
+		let mut id = 0;
 		unsafe {
-			gl_call("glDrawElements", || {
-				gl::DrawElements(
-					gl::TRIANGLES,
-					mesh.index_count,
-					mesh.index_type,
-					std::ptr::null_mut(),
+			gl::CreateBuffers(1, &mut id);
+			gl_call("glNamedBufferData uniform buffer", || {
+				gl::NamedBufferData(
+					id,
+					buffer.padded_size() as isize,
+					std::ptr::null(),
+					gl::DYNAMIC_DRAW,
+				);
+			});
+			gl_call("glBindBufferBase", || {
+				gl::BindBufferBase(gl::UNIFORM_BUFFER, binding, id);
+			});
+		}
+		let buffer = UniformBuffer { id, ..buffer };
+
+		for shader in self.shaders.values() {
+			let block = shader
+				.reflection
+				.uniform_blocks
+				.iter()
+				.find(|block| block.name == name);
+			if let Some(block) = block {
+				if buffer.padded_size() < block.size {
+					(self.error_callback)(&RendererError::UniformBlockSizeMismatch {
+						name: name.into(),
+						size: buffer.size,
+						expected: block.size,
+					});
+				}
+			}
+		}
+
+		let handle = UniformBufferHandle::new(self.handle_counter);
+		self.handle_counter += 1;
+		self.uniform_buffers.insert(handle, buffer);
+
+		Ok(handle)
+	}
+	fn update_uniform_buffer(
+		&mut self,
+		handle: UniformBufferHandle,
+		data: &[u8],
+	) -> Result<(), RendererError> {
+		let buffer = self
+			.uniform_buffers
+			.get(&handle)
+			.ok_or(RendererError::InvalidUniformBufferHandle(handle))?;
+		if data.len() != buffer.size {
+			return Err(RendererError::UniformBlockSizeMismatch {
+				name: buffer.name.clone(),
+				size: data.len(),
+				expected: buffer.size,
+			});
+		}
+		unsafe {
+			gl_call("glNamedBufferSubData uniform buffer", || {
+				gl::NamedBufferSubData(
+					buffer.id,
+					0,
+					data.len() as isize,
+					data.as_ptr() as *const _,
 				);
 			});
 		}
+		Ok(())
+	}
+	fn delete_uniform_buffer(&mut self, handle: UniformBufferHandle) -> Result<(), RendererError> {
+		let buffer = self
+			.uniform_buffers
+			.remove(&handle)
+			.ok_or(RendererError::InvalidUniformBufferHandle(handle))?;
+		self._delete_uniform_buffer(buffer);
+		Ok(())
+	}
+	fn create_texture(
+		&mut self,
+		size: (u32, u32),
+		format: TextureFormat,
+		data: Option<&[u8]>,
+		sampler: &SamplerState,
+	) -> Result<TextureHandle, RendererError> {
+		if let Some(data) = data {
+			texture::check_region(size, format, (0, 0), size, data)?;
+		}
+
+		let levels = if sampler.mipmaps {
+			32 - size.0.max(size.1).max(1).leading_zeros()
+		} else {
+			1
+		};
+		let (internal_format, _, _) = gl_texture_format(format);
+
+		let mut id = 0;
+		unsafe {
+			gl::CreateTextures(gl::TEXTURE_2D, 1, &mut id);
+			gl_call("glTextureStorage2D", || {
+				gl::TextureStorage2D(
+					id,
+					levels as i32,
+					internal_format,
+					size.0 as i32,
+					size.1 as i32,
+				);
+			});
+		}
+		let texture = Texture {
+			id,
+			size,
+			format,
+			levels,
+		};
+		if let Some(data) = data {
+			texture.upload((0, 0), size, data);
+		}
+		texture.apply_sampler(sampler);
+
+		let handle = TextureHandle::new(self.handle_counter);
+		self.handle_counter += 1;
+		self.textures.insert(handle, texture);
+
+		Ok(handle)
+	}
+	fn update_texture_region(
+		&mut self,
+		handle: TextureHandle,
+		offset: (u32, u32),
+		size: (u32, u32),
+		data: &[u8],
+	) -> Result<(), RendererError> {
+		let texture = self
+			.textures
+			.get(&handle)
+			.ok_or(RendererError::InvalidTextureHandle(handle))?;
+		texture::check_region(texture.size, texture.format, offset, size, data)?;
+		texture.upload(offset, size, data);
+		Ok(())
+	}
+	fn set_texture_sampler(
+		&mut self,
+		handle: TextureHandle,
+		sampler: &SamplerState,
+	) -> Result<(), RendererError> {
+		let texture = self
+			.textures
+			.get(&handle)
+			.ok_or(RendererError::InvalidTextureHandle(handle))?;
+		texture.apply_sampler(sampler);
+		Ok(())
+	}
+	fn texture_size(&self, handle: TextureHandle) -> Result<(u32, u32), RendererError> {
+		self.textures
+			.get(&handle)
+			.map(|texture| texture.size)
+			.ok_or(RendererError::InvalidTextureHandle(handle))
+	}
+	fn bind_texture(&mut self, handle: TextureHandle, unit: u32) -> Result<(), RendererError> {
+		if self.bound_textures.get(&unit) == Some(&handle) {
+			return Ok(());
+		}
+		let texture = self
+			.textures
+			.get(&handle)
+			.ok_or(RendererError::InvalidTextureHandle(handle))?;
+		unsafe {
+			gl_call("glBindTextureUnit", || {
+				gl::BindTextureUnit(unit, texture.id);
+			});
+		}
+		self.bound_textures.insert(unit, handle);
+		Ok(())
+	}
+	fn delete_texture(&mut self, handle: TextureHandle) -> Result<(), RendererError> {
+		if !self.textures.contains_key(&handle) {
+			return Err(RendererError::InvalidTextureHandle(handle));
+		}
+		let owner = self
+			.framebuffers
+			.iter()
+			.find(|(_, framebuffer)| framebuffer.textures.contains(&handle));
+		if let Some((&framebuffer, _)) = owner {
+			return Err(RendererError::TextureOwnedByFramebuffer {
+				texture: handle,
+				framebuffer,
+			});
+		}
+		self._delete_texture(handle);
+		Ok(())
+	}
+	fn create_framebuffer(
+		&mut self,
+		spec: &FramebufferSpec,
+	) -> Result<FramebufferHandle, RendererError> {
+		let textures = spec
+			.color
+			.iter()
+			.map(|format| self.create_texture(spec.size, *format, None, &spec.sampler))
+			.collect::<Result<Vec<_>, _>>()?;
+		let (id, resolve_id, renderbuffers) = match self.build_framebuffer(spec, &textures) {
+			Ok(objects) => objects,
+			Err(error) => {
+				for texture in textures {
+					self._delete_texture(texture);
+				}
+				return Err(error);
+			}
+		};
+
+		let handle = FramebufferHandle::new(self.handle_counter);
+		self.handle_counter += 1;
+		self.framebuffers.insert(
+			handle,
+			Framebuffer {
+				id,
+				resolve_id,
+				renderbuffers,
+				spec: spec.clone(),
+				textures,
+			},
+		);
+
+		Ok(handle)
+	}
+	fn resize_framebuffer(
+		&mut self,
+		handle: FramebufferHandle,
+		size: (u32, u32),
+	) -> Result<(), RendererError> {
+		let framebuffer = self
+			.framebuffers
+			.get(&handle)
+			.ok_or(RendererError::InvalidFramebufferHandle(handle))?;
+		let mut spec = framebuffer.spec.clone();
+		spec.size = size;
+		let textures = framebuffer.textures.clone();
+
+		// the framebuffer is only touched once the new one is complete
+		let mut resized = vec![];
+		for format in &spec.color {
+			match self.create_texture(size, *format, None, &spec.sampler) {
+				Ok(texture) => resized.push(texture),
+				Err(error) => {
+					for texture in resized {
+						self._delete_texture(texture);
+					}
+					return Err(error);
+				}
+			}
+		}
+		let (id, resolve_id, renderbuffers) = match self.build_framebuffer(&spec, &resized) {
+			Ok(objects) => objects,
+			Err(error) => {
+				for texture in resized {
+					self._delete_texture(texture);
+				}
+				return Err(error);
+			}
+		};
+
+		// the new storage moves in behind the old handles
+		for (&texture, new) in textures.iter().zip(resized) {
+			let new = self.textures.remove(&new).unwrap();
+			self._delete_texture(texture);
+			self.textures.insert(texture, new);
+		}
+		let framebuffer = self.framebuffers.get_mut(&handle).unwrap();
+		delete_framebuffer_objects(
+			framebuffer.id,
+			framebuffer.resolve_id,
+			&framebuffer.renderbuffers,
+		);
+		framebuffer.id = id;
+		framebuffer.resolve_id = resolve_id;
+		framebuffer.renderbuffers = renderbuffers;
+		framebuffer.spec = spec;
+
+		if handle == self.bound_framebuffer {
+			unsafe { gl::BindFramebuffer(gl::FRAMEBUFFER, id) };
+			self.set_viewport((0, 0), size);
+		}
+		Ok(())
+	}
+	fn bind_framebuffer(&mut self, handle: FramebufferHandle) -> Result<(), RendererError> {
+		if handle == self.bound_framebuffer {
+			return Ok(());
+		}
+		let framebuffer = self
+			.framebuffers
+			.get(&handle)
+			.ok_or(RendererError::InvalidFramebufferHandle(handle))?;
+		let (id, size) = (framebuffer.id, framebuffer.spec.size);
+
+		if self.bound_framebuffer == FramebufferHandle::none() {
+			self.default_viewport = self.viewport;
+		} else {
+			self.resolve_framebuffer(self.bound_framebuffer);
+		}
+		unsafe {
+			gl_call("glBindFramebuffer", || {
+				gl::BindFramebuffer(gl::FRAMEBUFFER, id);
+			});
+		}
+		self.set_viewport((0, 0), size);
+		self.bound_framebuffer = handle;
+		Ok(())
+	}
+	fn bind_default_framebuffer(&mut self) {
+		if self.bound_framebuffer == FramebufferHandle::none() {
+			return;
+		}
+		self.resolve_framebuffer(self.bound_framebuffer);
+		unsafe { gl::BindFramebuffer(gl::FRAMEBUFFER, 0) };
+		let (position, size) = self.default_viewport;
+		self.set_viewport(position, size);
+		self.bound_framebuffer = FramebufferHandle::none();
+	}
+	fn framebuffer_texture(
+		&self,
+		handle: FramebufferHandle,
+		index: usize,
+	) -> Result<TextureHandle, RendererError> {
+		self.framebuffers
+			.get(&handle)
+			.ok_or(RendererError::InvalidFramebufferHandle(handle))?
+			.textures
+			.get(index)
+			.copied()
+			.ok_or(RendererError::InvalidFramebufferAttachment { handle, index })
+	}
+	fn read_framebuffer(
+		&mut self,
+		handle: FramebufferHandle,
+		index: usize,
+		offset: (u32, u32),
+		size: (u32, u32),
+	) -> Result<Vec<u8>, RendererError> {
+		let texture_handle = self.framebuffer_texture(handle, index)?;
+		let texture = self
+			.textures
+			.get(&texture_handle)
+			.ok_or(RendererError::InvalidTextureHandle(texture_handle))?;
+		let format = texture.format;
+		let mut pixels = vec![0; size.0 as usize * size.1 as usize * format.bytes_per_pixel()];
+		texture::check_region(texture.size, format, offset, size, &pixels)?;
+
+		if handle == self.bound_framebuffer {
+			self.resolve_framebuffer(handle);
+		}
+		let framebuffer = &self.framebuffers[&handle];
+		let read_id = if framebuffer.resolve_id != 0 {
+			framebuffer.resolve_id
+		} else {
+			framebuffer.id
+		};
+		let (_, gl_format, type_) = gl_texture_format(format);
+		unsafe {
+			gl::NamedFramebufferReadBuffer(read_id, gl::COLOR_ATTACHMENT0 + index as u32);
+			gl::BindFramebuffer(gl::READ_FRAMEBUFFER, read_id);
+			gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+			gl_call("glReadPixels", || {
+				gl::ReadPixels(
+					offset.0 as i32,
+					offset.1 as i32,
+					size.0 as i32,
+					size.1 as i32,
+					gl_format,
+					type_,
+					pixels.as_mut_ptr() as *mut _,
+				);
+			});
+			gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.bound_framebuffer_id());
+		}
+		Ok(pixels)
+	}
+	fn delete_framebuffer(&mut self, handle: FramebufferHandle) -> Result<(), RendererError> {
+		if !self.framebuffers.contains_key(&handle) {
+			return Err(RendererError::InvalidFramebufferHandle(handle));
+		}
+		if handle == self.bound_framebuffer {
+			self.bind_default_framebuffer();
+		}
+		let framebuffer = self.framebuffers.remove(&handle).unwrap();
+		delete_framebuffer_objects(
+			framebuffer.id,
+			framebuffer.resolve_id,
+			&framebuffer.renderbuffers,
+		);
+		for texture in framebuffer.textures {
+			self._delete_texture(texture);
+		}
+		Ok(())
+	}
+	// draws with stale handles or out of bounds ranges are reported through the
+	// error callback and skipped
+	fn render_with(&mut self, mh: MeshHandle, sh: ShaderHandle, params: &DrawParams) {
+		if let Err(error) = self._render_with(mh, sh, params) {
+			(self.error_callback)(&error);
+		}
+	}
+	fn render_instanced(
+		&mut self,
+		mh: MeshHandle,
+		sh: ShaderHandle,
+		instances: InstanceBufferHandle,
+		count: usize,
+	) {
+		if let Err(error) = self._render_instanced(mh, sh, instances, count) {
+			(self.error_callback)(&error);
+		}
+	}
+	fn dispatch_compute(&mut self, sh: ShaderHandle, groups: (u32, u32, u32)) {
+		if let Err(error) = self._dispatch_compute(sh, groups) {
+			(self.error_callback)(&error);
+		}
 	}
 }
 
@@ -186,23 +1359,233 @@ type PartialShader = u32;
 struct Shader {
 	pub id: u32,
 	pub uniform_cache: HashMap<String, i32>,
+	pub uniforms: HashMap<String, ShaderUniform>,
+	pub path: PathBuf,
+	pub defines: Vec<(String, String)>,
+	pub sources: Vec<(PathBuf, Option<SystemTime>)>,
+	pub reflection: ShaderReflection,
+	// unit and texture of every texture uniform
+	pub textures: HashMap<String, (u32, TextureHandle)>,
+	// uniforms that have already been reported as invalid
+	pub reported: HashSet<String>,
+	pub fallback: bool,
 }
 
 impl Shader {
-	pub fn new(id: u32) -> Self {
-		Self {
+	pub fn new(
+		id: u32,
+		path: PathBuf,
+		defines: Vec<(String, String)>,
+		files: Vec<PathBuf>,
+	) -> Self {
+		let mut shader = Self {
 			id,
 			uniform_cache: Default::default(),
+			uniforms: Default::default(),
+			path,
+			defines,
+			sources: vec![],
+			reflection: reflect(id),
+			textures: Default::default(),
+			reported: Default::default(),
+			fallback: false,
+		};
+		shader.watch(files);
+		shader
+	}
+	pub fn watch(&mut self, files: Vec<PathBuf>) {
+		self.sources = files
+			.into_iter()
+			.map(|file| {
+				let modified = modified_time(&file);
+				(file, modified)
+			})
+			.collect();
+	}
+	// returns whether any of the source files changed since the last call
+	pub fn refresh_sources(&mut self) -> bool {
+		let mut changed = false;
+		for (file, modified) in &mut self.sources {
+			let now = modified_time(file);
+			if now != *modified {
+				*modified = now;
+				changed = true;
+			}
+		}
+		changed
+	}
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+	fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn reflect(program: u32) -> ShaderReflection {
+	let mut reflection = ShaderReflection::default();
+	unsafe {
+		let mut count = 0;
+		let mut max_len = 0;
+		gl::GetProgramiv(program, gl::ACTIVE_UNIFORMS, &mut count);
+		gl::GetProgramiv(program, gl::ACTIVE_UNIFORM_MAX_LENGTH, &mut max_len);
+		for i in 0..count as u32 {
+			let (name, type_, size) = active_variable(program, i, max_len, gl::GetActiveUniform);
+			let location =
+				gl::GetUniformLocation(program, CString::new(name.clone()).unwrap().as_ptr());
+			reflection.uniforms.push(UniformInfo {
+				name,
+				type_,
+				size,
+				location,
+			});
+		}
+
+		gl::GetProgramiv(program, gl::ACTIVE_UNIFORM_BLOCKS, &mut count);
+		gl::GetProgramiv(
+			program,
+			gl::ACTIVE_UNIFORM_BLOCK_MAX_NAME_LENGTH,
+			&mut max_len,
+		);
+		for i in 0..count as u32 {
+			let mut name = vec![0u8; max_len.max(1) as usize];
+			let mut len = 0;
+			gl::GetActiveUniformBlockName(
+				program,
+				i,
+				max_len,
+				&mut len,
+				name.as_mut_ptr() as *mut i8,
+			);
+			name.truncate(len as usize);
+			let mut size = 0;
+			gl::GetActiveUniformBlockiv(program, i, gl::UNIFORM_BLOCK_DATA_SIZE, &mut size);
+			reflection.uniform_blocks.push(UniformBlockInfo {
+				name: String::from_utf8_lossy(&name).into_owned(),
+				index: i,
+				size: size as usize,
+			});
+		}
+
+		gl::GetProgramiv(program, gl::ACTIVE_ATTRIBUTES, &mut count);
+		gl::GetProgramiv(program, gl::ACTIVE_ATTRIBUTE_MAX_LENGTH, &mut max_len);
+		for i in 0..count as u32 {
+			let (name, type_, size) = active_variable(program, i, max_len, gl::GetActiveAttrib);
+			let location =
+				gl::GetAttribLocation(program, CString::new(name.clone()).unwrap().as_ptr());
+			reflection.attributes.push(AttributeInfo {
+				name,
+				type_,
+				size,
+				location,
+			});
+		}
+	}
+	reflection
+}
+
+type GetActiveVariable = unsafe fn(u32, u32, i32, *mut i32, *mut i32, *mut u32, *mut i8);
+
+unsafe fn active_variable(
+	program: u32,
+	index: u32,
+	max_len: i32,
+	get: GetActiveVariable,
+) -> (String, UniformType, usize) {
+	let mut name = vec![0u8; max_len.max(1) as usize];
+	let mut len = 0;
+	let mut size = 0;
+	let mut type_ = 0;
+	get(
+		program,
+		index,
+		max_len,
+		&mut len,
+		&mut size,
+		&mut type_,
+		name.as_mut_ptr() as *mut i8,
+	);
+	name.truncate(len as usize);
+
+	let mut name = String::from_utf8_lossy(&name).into_owned();
+	// arrays are reported by their first element
+	if name.ends_with("[0]") {
+		name.truncate(name.len() - 3);
+	}
+
+	(name, uniform_type(type_), size as usize)
+}
+
+fn uniform_type(type_: u32) -> UniformType {
+	match type_ {
+		gl::FLOAT => UniformType::Float,
+		gl::FLOAT_VEC2 => UniformType::Vector2,
+		gl::FLOAT_VEC3 => UniformType::Vector3,
+		gl::FLOAT_VEC4 => UniformType::Vector4,
+		gl::INT => UniformType::Int,
+		gl::INT_VEC2 => UniformType::IVector2,
+		gl::INT_VEC3 => UniformType::IVector3,
+		gl::INT_VEC4 => UniformType::IVector4,
+		gl::UNSIGNED_INT => UniformType::UInt,
+		gl::UNSIGNED_INT_VEC2 => UniformType::UVector2,
+		gl::UNSIGNED_INT_VEC3 => UniformType::UVector3,
+		gl::UNSIGNED_INT_VEC4 => UniformType::UVector4,
+		gl::BOOL => UniformType::Bool,
+		gl::FLOAT_MAT2 => UniformType::Matrix2,
+		gl::FLOAT_MAT3 => UniformType::Matrix3,
+		gl::FLOAT_MAT4 => UniformType::Matrix4,
+		gl::SAMPLER_1D
+		| gl::SAMPLER_2D
+		| gl::SAMPLER_3D
+		| gl::SAMPLER_CUBE
+		| gl::SAMPLER_2D_SHADOW
+		| gl::SAMPLER_2D_ARRAY
+		| gl::SAMPLER_2D_ARRAY_SHADOW
+		| gl::SAMPLER_CUBE_SHADOW
+		| gl::SAMPLER_2D_MULTISAMPLE
+		| gl::SAMPLER_2D_RECT
+		| gl::SAMPLER_BUFFER
+		| gl::INT_SAMPLER_2D
+		| gl::INT_SAMPLER_3D
+		| gl::UNSIGNED_INT_SAMPLER_2D
+		| gl::UNSIGNED_INT_SAMPLER_3D => UniformType::Sampler,
+		other => UniformType::Other(other),
+	}
+}
+
+fn build_shader(stages: &[(ShaderType, String)]) -> Result<u32, RendererError> {
+	let mut compiled = vec![];
+	let mut errors = vec![];
+	for (type_, src) in stages {
+		match compile_shader(src, *type_) {
+			Ok(id) => compiled.push(id),
+			Err(log) => errors.push((*type_, log)),
+		}
+	}
+
+	let shader = if errors.is_empty() {
+		link_shaders(&compiled)
+	} else {
+		Err(RendererError::Compile(errors))
+	};
+
+	unsafe {
+		for id in compiled {
+			gl::DeleteShader(id);
 		}
 	}
+
+	shader
 }
 
-fn compile_shader(src: &str, type_: ShaderType) -> PartialShader {
+fn compile_shader(src: &str, type_: ShaderType) -> Result<PartialShader, String> {
 	unsafe {
 		let id = gl_call("glCreateShader", || {
 			gl::CreateShader(match type_ {
 				ShaderType::Vertex => gl::VERTEX_SHADER,
 				ShaderType::Fragment => gl::FRAGMENT_SHADER,
+				ShaderType::Geometry => gl::GEOMETRY_SHADER,
+				ShaderType::TessControl => gl::TESS_CONTROL_SHADER,
+				ShaderType::TessEvaluation => gl::TESS_EVALUATION_SHADER,
+				ShaderType::Compute => gl::COMPUTE_SHADER,
 			})
 		});
 		let ptr = src.as_ptr() as *const i8;
@@ -213,25 +1596,23 @@ fn compile_shader(src: &str, type_: ShaderType) -> PartialShader {
 		let mut success = 0;
 		gl::GetShaderiv(id, gl::COMPILE_STATUS, &mut success);
 		if success == 0 {
-			println!("\x1b[31m{} Shader Compilation Failiure:\x1b[0m", type_);
-
 			let mut len = 0;
 			gl::GetShaderiv(id, gl::INFO_LOG_LENGTH, &mut len);
 
-			let mut log = vec![b'a'; len as usize - 1];
+			let mut log = vec![0u8; len.max(1) as usize];
 			gl::GetShaderInfoLog(id, len, &mut len, log.as_mut_ptr() as *mut i8);
+			log.truncate(len as usize);
 
-			let log = String::from_utf8_lossy(&log);
+			gl::DeleteShader(id);
 
-			println!("{}", log);
-			panic!();
+			return Err(String::from_utf8_lossy(&log).into_owned());
 		}
 
-		id
+		Ok(id)
 	}
 }
 
-fn link_shaders(shaders: &[PartialShader]) -> Shader {
+fn link_shaders(shaders: &[PartialShader]) -> Result<u32, RendererError> {
 	unsafe {
 		let id = gl::CreateProgram();
 
@@ -242,125 +1623,412 @@ fn link_shaders(shaders: &[PartialShader]) -> Shader {
 		}
 		gl::LinkProgram(id);
 
+		for shader in shaders {
+			gl::DetachShader(id, *shader);
+		}
+
 		let mut success = 0;
 		gl::GetProgramiv(id, gl::LINK_STATUS, &mut success);
 		if success == 0 {
-			println!("\x1b[31mShader Program Linking Failiure:\x1b[0m");
-
 			let mut len = 0;
 			gl::GetProgramiv(id, gl::INFO_LOG_LENGTH, &mut len);
 
-			let mut log = Vec::<u8>::with_capacity(len as usize);
+			let mut log = vec![0u8; len.max(1) as usize];
 			gl::GetProgramInfoLog(id, len, &mut len, log.as_mut_ptr() as *mut i8);
+			log.truncate(len as usize);
 
-			let log = String::from_utf8_lossy(&log);
+			gl::DeleteProgram(id);
 
-			println!("{}", log);
-			panic!();
+			return Err(RendererError::Link(
+				String::from_utf8_lossy(&log).into_owned(),
+			));
 		}
 
-		for shader in shaders {
-			gl::DetachShader(id, *shader);
+		Ok(id)
+	}
+}
+
+struct Texture {
+	id: u32,
+	size: (u32, u32),
+	format: TextureFormat,
+	levels: u32,
+}
+
+impl Texture {
+	fn upload(&self, offset: (u32, u32), size: (u32, u32), data: &[u8]) {
+		let (_, format, type_) = gl_texture_format(self.format);
+		unsafe {
+			// rows of r8 and rgb8 data aren't 4 byte aligned
+			gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+			gl_call("glTextureSubImage2D", || {
+				gl::TextureSubImage2D(
+					self.id,
+					0,
+					offset.0 as i32,
+					offset.1 as i32,
+					size.0 as i32,
+					size.1 as i32,
+					format,
+					type_,
+					data.as_ptr() as *const _,
+				);
+			});
+			if self.levels > 1 {
+				gl::GenerateTextureMipmap(self.id);
+			}
+		}
+	}
+	fn apply_sampler(&self, sampler: &SamplerState) {
+		let filter = |filter| match filter {
+			TextureFilter::Nearest => gl::NEAREST,
+			TextureFilter::Linear => gl::LINEAR,
+		};
+		let wrap = |wrap| match wrap {
+			TextureWrap::Repeat => gl::REPEAT,
+			TextureWrap::MirroredRepeat => gl::MIRRORED_REPEAT,
+			TextureWrap::ClampToEdge => gl::CLAMP_TO_EDGE,
+			TextureWrap::ClampToBorder => gl::CLAMP_TO_BORDER,
+		};
+		// mipmaps can only be sampled if they were allocated
+		let min_filter = match (sampler.min_filter, sampler.mipmaps && self.levels > 1) {
+			(TextureFilter::Nearest, true) => gl::NEAREST_MIPMAP_NEAREST,
+			(TextureFilter::Linear, true) => gl::LINEAR_MIPMAP_LINEAR,
+			(min_filter, false) => filter(min_filter),
+		};
+		unsafe {
+			gl_call("glTextureParameteri", || {
+				gl::TextureParameteri(self.id, gl::TEXTURE_MIN_FILTER, min_filter as i32);
+				gl::TextureParameteri(
+					self.id,
+					gl::TEXTURE_MAG_FILTER,
+					filter(sampler.mag_filter) as i32,
+				);
+				gl::TextureParameteri(self.id, gl::TEXTURE_WRAP_S, wrap(sampler.wrap_s) as i32);
+				gl::TextureParameteri(self.id, gl::TEXTURE_WRAP_T, wrap(sampler.wrap_t) as i32);
+			});
+			if sampler.anisotropy > 1.0 {
+				gl::TextureParameterf(self.id, TEXTURE_MAX_ANISOTROPY, sampler.anisotropy);
+			}
 		}
+	}
+}
 
-		Shader::new(id)
+// internal format, pixel format and pixel type
+fn gl_texture_format(format: TextureFormat) -> (u32, u32, u32) {
+	match format {
+		TextureFormat::R8 => (gl::R8, gl::RED, gl::UNSIGNED_BYTE),
+		TextureFormat::Rg8 => (gl::RG8, gl::RG, gl::UNSIGNED_BYTE),
+		TextureFormat::Rgb8 => (gl::RGB8, gl::RGB, gl::UNSIGNED_BYTE),
+		TextureFormat::Rgba8 => (gl::RGBA8, gl::RGBA, gl::UNSIGNED_BYTE),
+		TextureFormat::R16F => (gl::R16F, gl::RED, gl::HALF_FLOAT),
+		TextureFormat::Rgb16F => (gl::RGB16F, gl::RGB, gl::HALF_FLOAT),
+		TextureFormat::Rgba16F => (gl::RGBA16F, gl::RGBA, gl::HALF_FLOAT),
+		TextureFormat::R32F => (gl::R32F, gl::RED, gl::FLOAT),
+		TextureFormat::Rgba32F => (gl::RGBA32F, gl::RGBA, gl::FLOAT),
+		TextureFormat::R32UI => (gl::R32UI, gl::RED_INTEGER, gl::UNSIGNED_INT),
 	}
 }
 
-// TODO: maybe make magenta shaders if something fails to load
-fn read_shader_source(path: &Path) -> (String, String) {
-	if path.is_dir() {
-		let vertex = path.join("vertex.glsl");
-		let fragment = path.join("fragment.glsl");
-		assert!(
-			vertex.is_file(),
-			"Could not find vertex.glsl in specified directory {}",
-			path.display(),
-		);
-		assert!(
-			fragment.is_file(),
-			"Could not find fragment.glsl in specified directory {}",
-			path.display(),
-		);
-		let vertex = fs::read_to_string(vertex).unwrap();
-		let fragment = fs::read_to_string(fragment).unwrap();
+// only touches what differs from old, or everything if there is no old state
+unsafe fn apply_render_state(old: Option<&RenderState>, new: &RenderState) {
+	let enable = |cap, enabled| {
+		if enabled {
+			gl::Enable(cap);
+		} else {
+			gl::Disable(cap);
+		}
+	};
 
-		(vertex, fragment)
-	} else {
-		let source = fs::read_to_string(path).expect(&format!(
-			"Error reading shader source file at {}",
-			path.display()
-		));
-
-		let mut vertex = String::new();
-		let mut fragment = String::new();
-
-		let mut dst = None;
-		for line in source.lines() {
-			if line.starts_with("#type") {
-				if line.starts_with("#type vertex") {
-					dst = Some(true);
-				} else if line.starts_with("#type fragment") {
-					dst = Some(false);
-				} else {
-					panic!("Unknown shader type {}", &line[5..]);
-				}
-			} else if let Some(dst) = dst {
-				if dst {
-					vertex.push_str(line);
-					vertex.push('\n');
+	if old.map_or(true, |old| old.depth_test != new.depth_test) {
+		enable(gl::DEPTH_TEST, new.depth_test);
+	}
+	if old.map_or(true, |old| old.depth_func != new.depth_func) {
+		gl::DepthFunc(gl_compare_func(new.depth_func));
+	}
+	if old.map_or(true, |old| old.cull != new.cull) {
+		enable(gl::CULL_FACE, new.cull != CullMode::None);
+		match new.cull {
+			CullMode::None => {}
+			CullMode::Back => gl::CullFace(gl::BACK),
+			CullMode::Front => gl::CullFace(gl::FRONT),
+		}
+	}
+	if old.map_or(true, |old| old.blend != new.blend) {
+		enable(gl::BLEND, new.blend != BlendMode::None);
+		let (equation, src, dst) = match new.blend {
+			BlendMode::None | BlendMode::Alpha => {
+				(gl::FUNC_ADD, gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA)
+			}
+			BlendMode::PremultipliedAlpha => (gl::FUNC_ADD, gl::ONE, gl::ONE_MINUS_SRC_ALPHA),
+			BlendMode::Additive => (gl::FUNC_ADD, gl::SRC_ALPHA, gl::ONE),
+			BlendMode::Multiply => (gl::FUNC_ADD, gl::DST_COLOR, gl::ZERO),
+			// the factors are ignored by min and max
+			BlendMode::Min => (gl::MIN, gl::ONE, gl::ONE),
+			BlendMode::Max => (gl::MAX, gl::ONE, gl::ONE),
+		};
+		gl::BlendEquation(equation);
+		gl::BlendFunc(src, dst);
+	}
+	if old.map_or(true, |old| old.scissor != new.scissor) {
+		enable(gl::SCISSOR_TEST, new.scissor.is_some());
+		if let Some((position, size)) = new.scissor {
+			gl::Scissor(position.0, position.1, size.0 as i32, size.1 as i32);
+		}
+	}
+	if old.map_or(true, |old| old.stencil != new.stencil) {
+		enable(gl::STENCIL_TEST, new.stencil.is_some());
+		if let Some(stencil) = new.stencil {
+			gl::StencilFunc(
+				gl_compare_func(stencil.func),
+				stencil.reference,
+				stencil.read_mask,
+			);
+			gl::StencilOp(
+				gl_stencil_op(stencil.stencil_fail),
+				gl_stencil_op(stencil.depth_fail),
+				gl_stencil_op(stencil.pass),
+			);
+		}
+	}
+	let write_mask = |state: &RenderState| state.stencil.map(|stencil| stencil.write_mask);
+	if old.map_or(true, |old| {
+		old.depth_write != new.depth_write
+			|| old.color_mask != new.color_mask
+			|| write_mask(old) != write_mask(new)
+	}) {
+		apply_write_masks(new);
+	}
+}
+
+unsafe fn apply_write_masks(state: &RenderState) {
+	let [r, g, b, a] = state.color_mask;
+	gl::DepthMask(state.depth_write as u8);
+	gl::ColorMask(r as u8, g as u8, b as u8, a as u8);
+	gl::StencilMask(state.stencil.map_or(!0, |stencil| stencil.write_mask));
+}
+
+fn gl_compare_func(func: CompareFunc) -> u32 {
+	match func {
+		CompareFunc::Never => gl::NEVER,
+		CompareFunc::Less => gl::LESS,
+		CompareFunc::Equal => gl::EQUAL,
+		CompareFunc::LessEqual => gl::LEQUAL,
+		CompareFunc::Greater => gl::GREATER,
+		CompareFunc::NotEqual => gl::NOTEQUAL,
+		CompareFunc::GreaterEqual => gl::GEQUAL,
+		CompareFunc::Always => gl::ALWAYS,
+	}
+}
+
+fn gl_stencil_op(op: StencilOp) -> u32 {
+	match op {
+		StencilOp::Keep => gl::KEEP,
+		StencilOp::Zero => gl::ZERO,
+		StencilOp::Replace => gl::REPLACE,
+		StencilOp::Increment => gl::INCR,
+		StencilOp::IncrementWrap => gl::INCR_WRAP,
+		StencilOp::Decrement => gl::DECR,
+		StencilOp::DecrementWrap => gl::DECR_WRAP,
+		StencilOp::Invert => gl::INVERT,
+	}
+}
+
+struct Framebuffer {
+	id: u32,
+	// multisampled framebuffers draw into renderbuffers and get resolved into
+	// the textures attached to this one, 0 otherwise
+	resolve_id: u32,
+	renderbuffers: Vec<u32>,
+	spec: FramebufferSpec,
+	textures: Vec<TextureHandle>,
+}
+
+fn create_renderbuffer(internal_format: u32, size: (u32, u32), samples: u32) -> u32 {
+	let mut id = 0;
+	unsafe {
+		gl::CreateRenderbuffers(1, &mut id);
+		gl_call("glNamedRenderbufferStorage", || {
+			if samples > 1 {
+				gl::NamedRenderbufferStorageMultisample(
+					id,
+					samples as i32,
+					internal_format,
+					size.0 as i32,
+					size.1 as i32,
+				);
+			} else {
+				gl::NamedRenderbufferStorage(id, internal_format, size.0 as i32, size.1 as i32);
+			}
+		});
+	}
+	id
+}
+
+fn delete_framebuffer_objects(id: u32, resolve_id: u32, renderbuffers: &[u32]) {
+	unsafe {
+		gl::DeleteFramebuffers(1, &id);
+		if resolve_id != 0 {
+			gl::DeleteFramebuffers(1, &resolve_id);
+		}
+		gl::DeleteRenderbuffers(renderbuffers.len() as i32, renderbuffers.as_ptr());
+	}
+}
+
+// internal format and attachment point
+fn gl_depth_format(format: DepthFormat) -> (u32, u32) {
+	match format {
+		DepthFormat::Depth24 => (gl::DEPTH_COMPONENT24, gl::DEPTH_ATTACHMENT),
+		DepthFormat::Depth32F => (gl::DEPTH_COMPONENT32F, gl::DEPTH_ATTACHMENT),
+		DepthFormat::Depth24Stencil8 => (gl::DEPTH24_STENCIL8, gl::DEPTH_STENCIL_ATTACHMENT),
+	}
+}
+
+struct UniformBuffer {
+	id: u32,
+	name: String,
+	size: usize,
+}
+
+impl UniformBuffer {
+	fn padded_size(&self) -> usize {
+		(self.size + 15) / 16 * 16
+	}
+}
+
+// array buffer that keeps its name when it grows, so vertex arrays using it
+// stay valid
+struct VertexBuffer {
+	id: u32,
+	usage: BufferUsage,
+	stride: usize,
+	// highest element written so far
+	count: usize,
+	// allocated size in bytes, may be larger than what was written last
+	capacity: usize,
+}
+
+impl VertexBuffer {
+	fn new(data: &Vertices, stride: usize, usage: BufferUsage) -> Self {
+		let mut id = 0;
+		unsafe {
+			gl::CreateBuffers(1, &mut id);
+			gl_call("glNamedBufferData array buffer", || {
+				gl::NamedBufferData(
+					id,
+					data.size() as isize,
+					data.as_ptr() as *const _,
+					gl_usage(usage),
+				);
+			});
+		}
+		Self {
+			id,
+			usage,
+			stride,
+			count: data.size() / stride,
+			capacity: data.size(),
+		}
+	}
+	// offset is in elements
+	fn write(&mut self, offset: usize, data: &Vertices) {
+		let offset = offset * self.stride;
+		let end = offset + data.size();
+		// whether the old contents were thrown away
+		let discarded;
+		unsafe {
+			if end > self.capacity {
+				let capacity = end.max(self.capacity * 2);
+				// the old contents are kept unless they're overwritten
+				discarded = offset == 0 && data.size() >= self.capacity;
+				if discarded {
+					self.allocate(capacity);
 				} else {
-					fragment.push_str(line);
-					fragment.push('\n');
+					self.grow(capacity);
 				}
+			} else if self.usage == BufferUsage::Stream && offset == 0 {
+				// orphan the storage so the driver doesn't have to wait for
+				// draws still reading last frame's data
+				discarded = true;
+				self.allocate(self.capacity);
+			} else {
+				discarded = false;
 			}
+			gl_call("glNamedBufferSubData array buffer", || {
+				gl::NamedBufferSubData(
+					self.id,
+					offset as isize,
+					data.size() as isize,
+					data.as_ptr() as *const _,
+				);
+			});
+		}
+		self.count = if discarded {
+			end / self.stride
+		} else {
+			self.count.max(end / self.stride)
+		};
+	}
+	unsafe fn allocate(&mut self, capacity: usize) {
+		gl_call("glNamedBufferData array buffer", || {
+			gl::NamedBufferData(
+				self.id,
+				capacity as isize,
+				std::ptr::null(),
+				gl_usage(self.usage),
+			);
+		});
+		self.capacity = capacity;
+	}
+	unsafe fn grow(&mut self, capacity: usize) {
+		let size = self.capacity as isize;
+		let mut copy = 0;
+		gl::CreateBuffers(1, &mut copy);
+		gl::NamedBufferData(copy, size, std::ptr::null(), gl::STREAM_COPY);
+		gl::CopyNamedBufferSubData(self.id, copy, 0, 0, size);
+		self.allocate(capacity);
+		gl::CopyNamedBufferSubData(copy, self.id, 0, 0, size);
+		gl::DeleteBuffers(1, &copy);
+	}
+	fn delete(self) {
+		unsafe {
+			gl::DeleteBuffers(1, &self.id);
 		}
-
-		(vertex, fragment)
 	}
 }
 
 struct Mesh {
 	va: u32,
-	vb: u32,
+	vb: VertexBuffer,
 	ib: u32,
 	index_count: i32,
 	index_type: u32,
+	// allocated size in bytes, may be larger than what was written last
+	index_capacity: usize,
+	// first attribute location after the mesh's own attributes
+	instance_location: u32,
+	// instance buffer whose attributes are currently set up in the vertex array
+	// and the location after its last attribute
+	instances: InstanceBufferHandle,
+	instance_end: u32,
 }
 
 impl Mesh {
-	pub fn new(vertices: &Vertices, layout: &BufferLayout, indices: &Indices) -> Self {
+	pub fn new(
+		vertices: &Vertices,
+		layout: &BufferLayout,
+		indices: &Indices,
+		usage: BufferUsage,
+	) -> Self {
 		unsafe {
 			let mut va = 0;
 			gl::CreateVertexArrays(1, &mut va);
 			gl::BindVertexArray(va);
 
-			let mut vb = 0;
-			gl::CreateBuffers(1, &mut vb);
-			gl::BindBuffer(gl::ARRAY_BUFFER, vb);
+			let vb = VertexBuffer::new(vertices, layout.stride, usage);
+			gl::BindBuffer(gl::ARRAY_BUFFER, vb.id);
 
-			gl_call("glBufferData array buffer", || {
-				gl::BufferData(
-					gl::ARRAY_BUFFER,
-					vertices.size() as isize,
-					vertices.as_ptr() as *const _,
-					gl::STATIC_DRAW,
-				);
-			});
-
-			for i in 0..layout.len {
-				gl_call("glEnableVertexAttribArray | glVertexAttribPointer", || {
-					gl::EnableVertexAttribArray(i as u32);
-					gl::VertexAttribPointer(
-						i as u32,
-						layout.counts[i] as i32,
-						gl::FLOAT, // data type
-						gl::FALSE, // normalize
-						layout.stride as i32,
-						layout.offsets[i] as _,
-					);
-				});
-			}
+			let instance_location = enable_attributes(layout, 0);
 
 			let mut ib = 0;
 			gl::CreateBuffers(1, &mut ib);
@@ -371,7 +2039,7 @@ impl Mesh {
 					gl::ELEMENT_ARRAY_BUFFER,
 					indices.size() as isize,
 					indices.as_ptr(),
-					gl::STATIC_DRAW,
+					gl_usage(usage),
 				);
 			});
 
@@ -380,14 +2048,129 @@ impl Mesh {
 				vb,
 				ib,
 				index_count: indices.len() as i32,
-				index_type: match indices {
-					Indices::U8(_) => gl::UNSIGNED_BYTE,
-					Indices::U16(_) => gl::UNSIGNED_SHORT,
-					Indices::U32(_) => gl::UNSIGNED_INT,
-				},
+				index_type: gl_index_type(indices),
+				index_capacity: indices.size(),
+				instance_location,
+				instances: InstanceBufferHandle::none(),
+				instance_end: instance_location,
 			}
 		}
 	}
+	fn write_indices(&mut self, indices: &Indices) {
+		unsafe {
+			if indices.size() > self.index_capacity || self.vb.usage == BufferUsage::Stream {
+				let capacity = indices.size().max(self.index_capacity);
+				gl_call("glNamedBufferData index buffer", || {
+					gl::NamedBufferData(
+						self.ib,
+						capacity as isize,
+						std::ptr::null(),
+						gl_usage(self.vb.usage),
+					);
+				});
+				self.index_capacity = capacity;
+			}
+			gl_call("glNamedBufferSubData index buffer", || {
+				gl::NamedBufferSubData(self.ib, 0, indices.size() as isize, indices.as_ptr());
+			});
+		}
+		self.index_count = indices.len() as i32;
+		self.index_type = gl_index_type(indices);
+	}
+}
+
+struct InstanceBuffer {
+	buffer: VertexBuffer,
+	layout: BufferLayout,
+}
+
+fn gl_usage(usage: BufferUsage) -> u32 {
+	match usage {
+		BufferUsage::Static => gl::STATIC_DRAW,
+		BufferUsage::Dynamic => gl::DYNAMIC_DRAW,
+		BufferUsage::Stream => gl::STREAM_DRAW,
+	}
+}
+
+fn gl_primitive(primitive: Primitive) -> u32 {
+	match primitive {
+		Primitive::Points => gl::POINTS,
+		Primitive::Lines => gl::LINES,
+		Primitive::LineStrip => gl::LINE_STRIP,
+		Primitive::Triangles => gl::TRIANGLES,
+		Primitive::TriangleStrip => gl::TRIANGLE_STRIP,
+		Primitive::TriangleFan => gl::TRIANGLE_FAN,
+	}
+}
+
+fn index_size(index_type: u32) -> usize {
+	match index_type {
+		gl::UNSIGNED_BYTE => 1,
+		gl::UNSIGNED_SHORT => 2,
+		_ => 4,
+	}
+}
+
+fn gl_index_type(indices: &Indices) -> u32 {
+	match indices {
+		Indices::U8(_) => gl::UNSIGNED_BYTE,
+		Indices::U16(_) => gl::UNSIGNED_SHORT,
+		Indices::U32(_) => gl::UNSIGNED_INT,
+	}
+}
+
+// sets up the attributes of the bound array buffer starting at location,
+// returns the location after the last one used
+unsafe fn enable_attributes(layout: &BufferLayout, mut location: u32) -> u32 {
+	for i in 0..layout.len {
+		let element = layout.types[i];
+		let type_ = match element {
+			BufferElement::Float
+			| BufferElement::Vector2
+			| BufferElement::Vector3
+			| BufferElement::Vector4
+			| BufferElement::Matrix4 => gl::FLOAT,
+			BufferElement::U8x4Norm | BufferElement::U8x4 => gl::UNSIGNED_BYTE,
+			BufferElement::I8x4Norm | BufferElement::I8x4 => gl::BYTE,
+			BufferElement::U16x2Norm | BufferElement::U16x2 => gl::UNSIGNED_SHORT,
+			BufferElement::I16x2Norm | BufferElement::I16x2 => gl::SHORT,
+			BufferElement::U32 => gl::UNSIGNED_INT,
+			BufferElement::I32 => gl::INT,
+		};
+		let slot_size = element.size() / element.slots();
+		for slot in 0..element.slots() {
+			let offset = layout.offsets[i] + slot * slot_size;
+			gl_call("glEnableVertexAttribArray | glVertexAttrib*Pointer", || {
+				gl::EnableVertexAttribArray(location);
+				if element.integer() {
+					gl::VertexAttribIPointer(
+						location,
+						layout.counts[i] as i32,
+						type_,
+						layout.stride as i32,
+						offset as _,
+					);
+				} else {
+					gl::VertexAttribPointer(
+						location,
+						layout.counts[i] as i32,
+						type_,
+						element.normalized() as u8,
+						layout.stride as i32,
+						offset as _,
+					);
+				}
+				gl::VertexAttribDivisor(location, layout.divisor);
+			});
+			location += 1;
+		}
+	}
+	location
+}
+
+// the whm vectors and matrices are tightly packed floats
+fn floats<T>(values: &[T]) -> *const f32 {
+	values.as_ptr() as *const f32
 }
 
 fn gl_call<T, F: FnOnce() -> T>(ident: &'static str, f: F) -> T {
@@ -1,50 +1,113 @@
-use crate::{events::*, input::get_mouse_position, Button, Event, Float, WindowProps};
+use crate::{
+	events::*, input::get_mouse_position, BackendError, Button, Event, WindowBackend, WindowProps,
+};
+
+use x11::{glx, xlib};
 
 use std::ffi::CString;
+use std::os::raw::{c_char, c_int, c_uint, c_ulong};
+use std::ptr;
 
 pub struct X11Window {
-	display: *mut x11::xlib::Display,
-	window: u64,
+	display: *mut xlib::Display,
+	window: xlib::Window,
+	colormap: xlib::Colormap,
+	context: glx::GLXContext,
+	im: xlib::XIM,
+	ic: xlib::XIC,
+	wm_delete_window: xlib::Atom,
 	title: String,
 	size: (u32, u32),
 	close_requested: bool,
 }
 
 impl X11Window {
-	pub fn new(props: WindowProps) -> Self {
+	pub fn new(props: WindowProps) -> Result<Self, BackendError> {
 		unsafe {
-			use x11::xlib::*;
+			use xlib::*;
 
-			let display = XOpenDisplay(std::ptr::null());
-			assert!(
-				display != std::ptr::null_mut(),
-				"XOpenDisplay returned null"
-			);
+			let display = XOpenDisplay(ptr::null());
+			if display.is_null() {
+				return Err(window_error("Could not open the X display"));
+			}
+
+			let screen_id = XDefaultScreen(display);
+			let root = XRootWindow(display, screen_id);
 
-			let screen = XDefaultScreenOfDisplay(display);
-			assert!(
-				display != std::ptr::null_mut(),
-				"XDefaultScreenOfDisplay returned null"
+			let fb_attributes = [
+				glx::GLX_X_RENDERABLE,
+				True,
+				glx::GLX_DRAWABLE_TYPE,
+				glx::GLX_WINDOW_BIT,
+				glx::GLX_RENDER_TYPE,
+				glx::GLX_RGBA_BIT,
+				glx::GLX_RED_SIZE,
+				8,
+				glx::GLX_GREEN_SIZE,
+				8,
+				glx::GLX_BLUE_SIZE,
+				8,
+				glx::GLX_ALPHA_SIZE,
+				8,
+				glx::GLX_DEPTH_SIZE,
+				24,
+				glx::GLX_STENCIL_SIZE,
+				8,
+				glx::GLX_DOUBLEBUFFER,
+				True,
+				0,
+			];
+			let mut config_count = 0;
+			let configs = glx::glXChooseFBConfig(
+				display,
+				screen_id,
+				fb_attributes.as_ptr(),
+				&mut config_count,
 			);
+			if configs.is_null() || config_count <= 0 {
+				if !configs.is_null() {
+					XFree(configs as *mut _);
+				}
+				XCloseDisplay(display);
+				return Err(window_error(
+					"glXChooseFBConfig found no usable framebuffer config",
+				));
+			}
+			let fb_config = *configs;
+			XFree(configs as *mut _);
 
-			let screen_id = XDefaultScreen(display);
-			// assert!();
-
-			let white = XWhitePixel(display, screen_id);
-			let black = XBlackPixel(display, screen_id);
-			let root = XRootWindowOfScreen(screen);
-			let x = 0;
-			let y = 0;
-			let window = XCreateSimpleWindow(
+			let visual = glx::glXGetVisualFromFBConfig(display, fb_config);
+			if visual.is_null() {
+				XCloseDisplay(display);
+				return Err(window_error("glXGetVisualFromFBConfig returned null"));
+			}
+
+			let colormap = XCreateColormap(display, root, (*visual).visual, AllocNone);
+
+			let mut window_attributes: XSetWindowAttributes = std::mem::zeroed();
+			window_attributes.colormap = colormap;
+			window_attributes.event_mask = KeyPressMask
+				| KeyReleaseMask
+				| KeymapStateMask
+				| ButtonPressMask
+				| ButtonReleaseMask
+				| PointerMotionMask
+				| StructureNotifyMask
+				| FocusChangeMask;
+
+			let window = XCreateWindow(
 				display,
 				root,
-				x,
-				y,
+				0,
+				0,
 				props.size.0,
 				props.size.1,
-				1,
-				white,
-				black,
+				0,
+				(*visual).depth,
+				InputOutput as c_uint,
+				(*visual).visual,
+				CWColormap | CWEventMask,
+				&mut window_attributes,
 			);
 
 			let title =
@@ -53,31 +116,121 @@ impl X11Window {
 			XStoreName(display, window, title.as_ptr());
 			XSetIconName(display, window, title.as_ptr());
 
-			XSelectInput(
+			// ask the window manager to send a message instead of killing the connection
+			let wm_delete_window = XInternAtom(
 				display,
-				window,
-				KeyPressMask | KeyReleaseMask | KeymapStateMask,
+				b"WM_DELETE_WINDOW\0".as_ptr() as *const c_char,
+				False,
 			);
+			let mut protocols = [wm_delete_window];
+			XSetWMProtocols(display, window, protocols.as_mut_ptr(), 1);
+
+			// without this X sends a release before every repeated press
+			XkbSetDetectableAutoRepeat(display, True, ptr::null_mut());
+
+			XSetLocaleModifiers(b"\0".as_ptr() as *const c_char);
+			let im = XOpenIM(display, ptr::null_mut(), ptr::null_mut(), ptr::null_mut());
+			let ic = if im.is_null() {
+				ptr::null_mut()
+			} else {
+				XCreateIC(
+					im,
+					XNInputStyle_0.as_ptr(),
+					(XIMPreeditNothing | XIMStatusNothing) as c_ulong,
+					XNClientWindow_0.as_ptr(),
+					window,
+					XNFocusWindow_0.as_ptr(),
+					window,
+					ptr::null_mut::<c_char>(),
+				)
+			};
+
+			XFree(visual as *mut _);
+			let context = create_context(display, fb_config);
+			if context.is_null() {
+				if !ic.is_null() {
+					XDestroyIC(ic);
+				}
+				if !im.is_null() {
+					XCloseIM(im);
+				}
+				XDestroyWindow(display, window);
+				XFreeColormap(display, colormap);
+				XCloseDisplay(display);
+				return Err(window_error("Could not create a GLX context"));
+			}
 
-			XClearWindow(display, window);
 			XMapRaised(display, window);
-			Self {
+			glx::glXMakeCurrent(display, window, context);
+
+			// TODO: OpenGL specific code should not be here
+			gl::load_with(|s| {
+				let name = CString::new(s).unwrap();
+				match glx::glXGetProcAddress(name.as_ptr() as *const u8) {
+					Some(f) => f as *const _,
+					None => ptr::null(),
+				}
+			});
+
+			Ok(Self {
 				display,
 				window,
+				colormap,
+				context,
+				im,
+				ic,
+				wm_delete_window,
 				title: props.title,
 				size: props.size,
 				close_requested: false,
-			}
+			})
 		}
 	}
+	unsafe fn lookup_text(&self, event: &mut xlib::XKeyEvent) -> String {
+		let mut buffer = [0u8; 32];
+		let len = if self.ic.is_null() {
+			xlib::XLookupString(
+				event,
+				buffer.as_mut_ptr() as *mut c_char,
+				buffer.len() as c_int,
+				ptr::null_mut(),
+				ptr::null_mut(),
+			)
+		} else {
+			let mut status = 0;
+			let len = xlib::Xutf8LookupString(
+				self.ic,
+				event,
+				buffer.as_mut_ptr() as *mut c_char,
+				buffer.len() as c_int,
+				ptr::null_mut(),
+				&mut status,
+			);
+			if status != xlib::XLookupChars && status != xlib::XLookupBoth {
+				0
+			} else {
+				len
+			}
+		};
+		String::from_utf8_lossy(&buffer[..len.max(0) as usize]).into_owned()
+	}
 }
 
 impl Drop for X11Window {
 	fn drop(&mut self) {
 		unsafe {
-			use x11::xlib::*;
+			use xlib::*;
 
+			glx::glXMakeCurrent(self.display, 0, ptr::null_mut());
+			glx::glXDestroyContext(self.display, self.context);
+			if !self.ic.is_null() {
+				XDestroyIC(self.ic);
+			}
+			if !self.im.is_null() {
+				XCloseIM(self.im);
+			}
 			XDestroyWindow(self.display, self.window);
+			XFreeColormap(self.display, self.colormap);
 			XCloseDisplay(self.display);
 		}
 	}
@@ -87,7 +240,7 @@ impl crate::Window for X11Window {
 	fn set_title(&mut self, title: String) {
 		self.title = title;
 		unsafe {
-			use x11::xlib::*;
+			use xlib::*;
 			let title =
 				CString::new(self.title.clone()).expect("Window title had a null byte in it");
 			XStoreName(self.display, self.window, title.as_ptr());
@@ -103,35 +256,86 @@ impl crate::Window for X11Window {
 	fn close_requested(&self) -> bool {
 		self.close_requested
 	}
+	fn swap_buffers(&mut self) {
+		unsafe {
+			glx::glXSwapBuffers(self.display, self.window);
+		}
+	}
 	fn update(&mut self) -> Vec<Box<dyn Event>> {
 		let mut events: Vec<Box<dyn Event>> = vec![];
 
 		unsafe {
-			use x11::xlib;
+			while xlib::XPending(self.display) > 0 {
+				let mut event: xlib::XEvent = std::mem::zeroed();
+				xlib::XNextEvent(self.display, &mut event);
 
-			let mut event = xlib::XEvent { type_: 0 };
+				if xlib::XFilterEvent(&mut event, 0) != 0 {
+					continue;
+				}
 
-			xlib::XNextEvent(self.display, &mut event as *mut xlib::XEvent);
+				match event.type_ {
+					xlib::MappingNotify => {
+						xlib::XRefreshKeyboardMapping(&mut event.mapping);
+					}
+					xlib::FocusIn => {
+						if !self.ic.is_null() {
+							xlib::XSetICFocus(self.ic);
+						}
+					}
+					xlib::FocusOut => {
+						if !self.ic.is_null() {
+							xlib::XUnsetICFocus(self.ic);
+						}
+					}
+					xlib::KeyPress => {
+						let button = convert_key_event(&mut event.key);
+						events.push(KeyPressedEvent::boxed(button, button.is_pressed()));
 
-			match event.type_ {
-				xlib::KeymapNotify => {
-					xlib::XRefreshKeyboardMapping(&mut event.mapping as *mut xlib::XMappingEvent);
-				}
-				xlib::KeyPress => {
-					let mut typed = [0u8; 25];
-					let mut keysym: xlib::KeySym = 0;
-					let len = xlib::XLookupString(
-						&mut event.key as *mut xlib::XKeyEvent,
-						typed.as_mut_ptr() as *mut i8,
-						typed.len() as _,
-						&mut keysym as *mut xlib::KeySym,
-						std::ptr::null_mut(),
-					);
-
-					let button = convert_key_event(keysym);
-					events.push(KeyPressedEvent::boxed(button, button.is_pressed()));
+						for which in self.lookup_text(&mut event.key).chars() {
+							events.push(TextWrittenEvent::boxed(which));
+						}
+					}
+					xlib::KeyRelease => {
+						events.push(KeyReleasedEvent::boxed(convert_key_event(&mut event.key)));
+					}
+					xlib::ButtonPress => match event.button.button {
+						4 => events.push(MouseScrolledEvent::boxed((0.0, 1.0))),
+						5 => events.push(MouseScrolledEvent::boxed((0.0, -1.0))),
+						6 => events.push(MouseScrolledEvent::boxed((-1.0, 0.0))),
+						7 => events.push(MouseScrolledEvent::boxed((1.0, 0.0))),
+						button => events.push(MouseDownEvent::boxed(convert_mouse_button(button))),
+					},
+					xlib::ButtonRelease => match event.button.button {
+						4..=7 => {}
+						button => events.push(MouseUpEvent::boxed(convert_mouse_button(button))),
+					},
+					xlib::MotionNotify => {
+						let pos: (u32, u32) =
+							(event.motion.x.max(0) as u32, event.motion.y.max(0) as u32);
+						let old_pos = get_mouse_position();
+						let delta: (i32, i32) = (
+							pos.0 as i32 - old_pos.0 as i32,
+							pos.1 as i32 - old_pos.1 as i32,
+						);
+						events.push(MouseMoveEvent::boxed(pos, delta));
+					}
+					xlib::ConfigureNotify => {
+						let size = (event.configure.width as u32, event.configure.height as u32);
+						if size != self.size {
+							self.size = size;
+							events.push(WindowResizedEvent::boxed(size));
+						}
+					}
+					xlib::ClientMessage => {
+						if event.client_message.data.get_long(0) as xlib::Atom
+							== self.wm_delete_window
+						{
+							self.close_requested = true;
+							events.push(WindowCloseRequestedEvent::boxed());
+						}
+					}
+					_ => {}
 				}
-				_ => {}
 			}
 		}
 
@@ -139,35 +343,248 @@ impl crate::Window for X11Window {
 	}
 }
 
-fn convert_key_event(keysym: x11::xlib::KeySym) -> Button {
-	use x11::keysym;
-	match keysym as u32 {
-		keysym::XK_a | keysym::XK_A => Button::A,
-		keysym::XK_b | keysym::XK_B => Button::B,
-		keysym::XK_c | keysym::XK_C => Button::C,
-		keysym::XK_d | keysym::XK_D => Button::D,
-		keysym::XK_e | keysym::XK_E => Button::E,
-		keysym::XK_f | keysym::XK_F => Button::F,
-		keysym::XK_g | keysym::XK_G => Button::G,
-		keysym::XK_h | keysym::XK_H => Button::H,
-		keysym::XK_i | keysym::XK_I => Button::I,
-		keysym::XK_j | keysym::XK_J => Button::J,
-		keysym::XK_k | keysym::XK_K => Button::K,
-		keysym::XK_l | keysym::XK_L => Button::L,
-		keysym::XK_m | keysym::XK_M => Button::M,
-		keysym::XK_n | keysym::XK_N => Button::N,
-		keysym::XK_o | keysym::XK_O => Button::O,
-		keysym::XK_p | keysym::XK_P => Button::P,
-		keysym::XK_q | keysym::XK_Q => Button::Q,
-		keysym::XK_r | keysym::XK_R => Button::R,
-		keysym::XK_s | keysym::XK_S => Button::S,
-		keysym::XK_t | keysym::XK_T => Button::T,
-		keysym::XK_u | keysym::XK_U => Button::U,
-		keysym::XK_v | keysym::XK_V => Button::V,
-		keysym::XK_w | keysym::XK_W => Button::W,
-		keysym::XK_x | keysym::XK_X => Button::X,
-		keysym::XK_y | keysym::XK_Y => Button::Y,
-		keysym::XK_z | keysym::XK_Z => Button::Z,
+fn window_error(message: &str) -> BackendError {
+	BackendError::WindowCreation(WindowBackend::X11, message.into())
+}
+
+type GlXCreateContextAttribsARB = unsafe extern "C" fn(
+	*mut xlib::Display,
+	glx::GLXFBConfig,
+	glx::GLXContext,
+	xlib::Bool,
+	*const c_int,
+) -> glx::GLXContext;
+
+unsafe fn create_context(
+	display: *mut xlib::Display,
+	fb_config: glx::GLXFBConfig,
+) -> glx::GLXContext {
+	use glx::arb::*;
+
+	let create_context_attribs = glx::glXGetProcAddress(b"glXCreateContextAttribsARB\0".as_ptr());
+	match create_context_attribs {
+		Some(f) => {
+			let f: GlXCreateContextAttribsARB = std::mem::transmute(f);
+			let attributes = [
+				GLX_CONTEXT_MAJOR_VERSION_ARB,
+				4,
+				GLX_CONTEXT_MINOR_VERSION_ARB,
+				5,
+				GLX_CONTEXT_PROFILE_MASK_ARB,
+				GLX_CONTEXT_CORE_PROFILE_BIT_ARB,
+				0,
+			];
+			f(
+				display,
+				fb_config,
+				ptr::null_mut(),
+				xlib::True,
+				attributes.as_ptr(),
+			)
+		}
+		None => glx::glXCreateNewContext(
+			display,
+			fb_config,
+			glx::GLX_RGBA_TYPE,
+			ptr::null_mut(),
+			xlib::True,
+		),
+	}
+}
+
+fn convert_mouse_button(button: c_uint) -> Button {
+	match button {
+		xlib::Button1 => Button::MouseLeft,
+		xlib::Button2 => Button::MouseMiddle,
+		xlib::Button3 => Button::MouseRight,
+		8 => Button::Mouse4,
+		9 => Button::Mouse5,
 		_ => Button::Unknown,
 	}
 }
+
+unsafe fn convert_key_event(event: &mut xlib::XKeyEvent) -> Button {
+	use x11::keysym::*;
+
+	let keysym = xlib::XLookupKeysym(event, 0) as c_uint;
+	// the unshifted keypad keysyms are the navigation keys, the digits are at index 1
+	let keysym = if keysym >= XK_KP_Space && keysym <= XK_KP_9 {
+		match xlib::XLookupKeysym(event, 1) as c_uint {
+			0 => keysym,
+			shifted => shifted,
+		}
+	} else {
+		keysym
+	};
+
+	match keysym {
+		XK_space => Button::Space,
+		XK_apostrophe => Button::Apostrophe,
+		XK_comma => Button::Comma,
+		XK_minus => Button::Minus,
+		XK_period => Button::Period,
+		XK_slash => Button::Slash,
+		XK_grave => Button::Tilde,
+		XK_0 => Button::Num0,
+		XK_1 => Button::Num1,
+		XK_2 => Button::Num2,
+		XK_3 => Button::Num3,
+		XK_4 => Button::Num4,
+		XK_5 => Button::Num5,
+		XK_6 => Button::Num6,
+		XK_7 => Button::Num7,
+		XK_8 => Button::Num8,
+		XK_9 => Button::Num9,
+		XK_semicolon => Button::Semicolon,
+		XK_equal => Button::Equals,
+		XK_a | XK_A => Button::A,
+		XK_b | XK_B => Button::B,
+		XK_c | XK_C => Button::C,
+		XK_d | XK_D => Button::D,
+		XK_e | XK_E => Button::E,
+		XK_f | XK_F => Button::F,
+		XK_g | XK_G => Button::G,
+		XK_h | XK_H => Button::H,
+		XK_i | XK_I => Button::I,
+		XK_j | XK_J => Button::J,
+		XK_k | XK_K => Button::K,
+		XK_l | XK_L => Button::L,
+		XK_m | XK_M => Button::M,
+		XK_n | XK_N => Button::N,
+		XK_o | XK_O => Button::O,
+		XK_p | XK_P => Button::P,
+		XK_q | XK_Q => Button::Q,
+		XK_r | XK_R => Button::R,
+		XK_s | XK_S => Button::S,
+		XK_t | XK_T => Button::T,
+		XK_u | XK_U => Button::U,
+		XK_v | XK_V => Button::V,
+		XK_w | XK_W => Button::W,
+		XK_x | XK_X => Button::X,
+		XK_y | XK_Y => Button::Y,
+		XK_z | XK_Z => Button::Z,
+		XK_bracketleft => Button::BracketLeft,
+		XK_backslash => Button::Backslash,
+		XK_bracketright => Button::BracketRight,
+		XK_Escape => Button::Escape,
+		XK_Return => Button::Enter,
+		XK_Tab => Button::Tab,
+		XK_BackSpace => Button::Backspace,
+		XK_Insert => Button::Insert,
+		XK_Delete => Button::Delete,
+		XK_Right => Button::ArrowRight,
+		XK_Left => Button::ArrowLeft,
+		XK_Down => Button::ArrowDown,
+		XK_Up => Button::ArrowUp,
+		XK_Prior => Button::PgUp,
+		XK_Next => Button::PgDown,
+		XK_Home => Button::Home,
+		XK_End => Button::End,
+		XK_Caps_Lock => Button::CapsLock,
+		XK_Scroll_Lock => Button::ScrollLock,
+		XK_Num_Lock => Button::NumLock,
+		XK_Print => Button::PrintScreen,
+		XK_Pause => Button::Pause,
+		XK_F1 => Button::F1,
+		XK_F2 => Button::F2,
+		XK_F3 => Button::F3,
+		XK_F4 => Button::F4,
+		XK_F5 => Button::F5,
+		XK_F6 => Button::F6,
+		XK_F7 => Button::F7,
+		XK_F8 => Button::F8,
+		XK_F9 => Button::F9,
+		XK_F10 => Button::F10,
+		XK_F11 => Button::F11,
+		XK_F12 => Button::F12,
+		XK_KP_0 | XK_KP_Insert => Button::NumPad0,
+		XK_KP_1 | XK_KP_End => Button::NumPad1,
+		XK_KP_2 | XK_KP_Down => Button::NumPad2,
+		XK_KP_3 | XK_KP_Page_Down => Button::NumPad3,
+		XK_KP_4 | XK_KP_Left => Button::NumPad4,
+		XK_KP_5 | XK_KP_Begin => Button::NumPad5,
+		XK_KP_6 | XK_KP_Right => Button::NumPad6,
+		XK_KP_7 | XK_KP_Home => Button::NumPad7,
+		XK_KP_8 | XK_KP_Up => Button::NumPad8,
+		XK_KP_9 | XK_KP_Page_Up => Button::NumPad9,
+		XK_KP_Decimal | XK_KP_Delete => Button::NumPadDec,
+		XK_KP_Divide => Button::NumPadDiv,
+		XK_KP_Multiply => Button::NumPadMult,
+		XK_KP_Subtract => Button::NumPadSub,
+		XK_KP_Add => Button::NumPadAdd,
+		XK_KP_Enter => Button::NumPadEnter,
+		XK_KP_Equal => Button::NumPadEq,
+		XK_Shift_L => Button::LShift,
+		XK_Control_L => Button::LCtrl,
+		XK_Alt_L => Button::LAlt,
+		XK_Super_L => Button::LSuper,
+		XK_Shift_R => Button::RShift,
+		XK_Control_R => Button::RCtrl,
+		XK_Alt_R => Button::RAlt,
+		XK_Super_R => Button::RSuper,
+		XK_Menu => Button::Menu,
+		_ => Button::Unknown,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{events::EventType, Window};
+
+	use std::{thread, time::Duration};
+
+	// needs an X server with GLX, like xvfb-run cargo test --features x11 -- --ignored
+	#[test]
+	#[ignore]
+	fn x11_window() {
+		let mut window = X11Window::new(WindowProps {
+			title: "test".into(),
+			size: (80, 60),
+		})
+		.unwrap();
+		window.update();
+		assert_eq!(window.get_size(), (80, 60));
+		assert!(!window.close_requested());
+
+		unsafe {
+			xlib::XResizeWindow(window.display, window.window, 100, 70);
+			// what the window manager sends when the window is closed
+			let mut event: xlib::XEvent = std::mem::zeroed();
+			event.client_message.type_ = xlib::ClientMessage;
+			event.client_message.window = window.window;
+			event.client_message.message_type = xlib::XInternAtom(
+				window.display,
+				b"WM_PROTOCOLS\0".as_ptr() as *const c_char,
+				xlib::False,
+			);
+			event.client_message.format = 32;
+			event
+				.client_message
+				.data
+				.set_long(0, window.wm_delete_window as _);
+			xlib::XSendEvent(
+				window.display,
+				window.window,
+				xlib::False,
+				xlib::NoEventMask,
+				&mut event,
+			);
+			xlib::XSync(window.display, xlib::False);
+		}
+
+		let mut events = vec![];
+		for _ in 0..100 {
+			events.extend(window.update());
+			if window.close_requested() && window.get_size() == (100, 70) {
+				break;
+			}
+			thread::sleep(Duration::from_millis(10));
+		}
+		assert!(window.close_requested());
+		assert_eq!(window.get_size(), (100, 70));
+		let types = events.iter().map(|e| e.event_type()).collect::<Vec<_>>();
+		assert!(types.contains(&EventType::WindowResized));
+		assert!(types.contains(&EventType::WindowCloseRequested));
+	}
+}
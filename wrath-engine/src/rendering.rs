@@ -1,10 +1,41 @@
 mod renderer;
 pub use renderer::Renderer;
 
+use crate::RendererBackend;
+
 pub mod shader;
 
+pub mod error;
+
+pub(crate) mod preprocessor;
+
+pub mod reflection;
+
 pub mod mesh;
 
+pub mod vertex;
+
+pub mod recording_renderer;
+
+pub mod uniform_buffer;
+
+pub mod texture;
+
+pub mod framebuffer;
+
+pub mod render_state;
+
+pub mod renderer_2d;
+
+pub mod font;
+
+pub fn create(backend: RendererBackend) -> Box<dyn Renderer> {
+	match backend {
+		RendererBackend::OpenGL => box crate::platform::opengl_renderer::OpenGLRenderer::new(),
+		RendererBackend::Recording => box recording_renderer::RecordingRenderer::new(),
+	}
+}
+
 // TODO: create "Bindable" trait so you can
 // do renderer.bind(my_shit) with anything
 // bindable
@@ -0,0 +1,272 @@
+use super::{
+	framebuffer::FramebufferHandle,
+	mesh::{DrawRange, InstanceBufferHandle, MeshHandle},
+	reflection::UniformType,
+	shader::ShaderHandle,
+	shader::ShaderType,
+	texture::TextureHandle,
+	uniform_buffer::UniformBufferHandle,
+};
+
+use std::{error::Error, fmt, io, path::PathBuf};
+
+#[derive(Debug)]
+pub enum RendererError {
+	Io {
+		path: PathBuf,
+		error: io::Error,
+	},
+	Image {
+		path: PathBuf,
+		error: image::ImageError,
+	},
+	Font {
+		path: PathBuf,
+		error: rusttype::Error,
+	},
+	UnknownShaderType {
+		path: PathBuf,
+		name: String,
+	},
+	IncludeCycle(Vec<PathBuf>),
+	MalformedInclude {
+		path: PathBuf,
+		line: usize,
+	},
+	InvalidStages {
+		path: PathBuf,
+		stages: Vec<ShaderType>,
+	},
+	Compile(Vec<(ShaderType, String)>),
+	Link(String),
+	VertexLayoutMismatch {
+		size: usize,
+		stride: usize,
+	},
+	InvalidShaderHandle(ShaderHandle),
+	InvalidMeshHandle(MeshHandle),
+	DrawRangeOutOfBounds {
+		mesh: MeshHandle,
+		range: DrawRange,
+		len: usize,
+	},
+	InvalidInstanceBufferHandle(InstanceBufferHandle),
+	InstanceCountOutOfBounds {
+		instances: InstanceBufferHandle,
+		count: usize,
+		len: usize,
+	},
+	InvalidUniformBufferHandle(UniformBufferHandle),
+	UniformBlockSizeMismatch {
+		name: String,
+		size: usize,
+		expected: usize,
+	},
+	UniformBlockLayoutMismatch {
+		name: String,
+		offsets: Vec<usize>,
+		expected: Vec<usize>,
+	},
+	InvalidTextureHandle(TextureHandle),
+	TextureDataMismatch {
+		size: usize,
+		expected: usize,
+	},
+	TextureRegionOutOfBounds {
+		offset: (u32, u32),
+		size: (u32, u32),
+		texture_size: (u32, u32),
+	},
+	// with the width and height of the atlas texture
+	FontAtlasFull {
+		texture: TextureHandle,
+		size: u32,
+	},
+	InvalidFramebufferHandle(FramebufferHandle),
+	InvalidFramebufferAttachment {
+		handle: FramebufferHandle,
+		index: usize,
+	},
+	// attachments are deleted with their framebuffer
+	TextureOwnedByFramebuffer {
+		texture: TextureHandle,
+		framebuffer: FramebufferHandle,
+	},
+	// with the backend's status code
+	IncompleteFramebuffer(u32),
+	UnknownUniform {
+		handle: ShaderHandle,
+		name: String,
+	},
+	UniformTypeMismatch {
+		handle: ShaderHandle,
+		name: String,
+		expected: UniformType,
+	},
+}
+
+impl fmt::Display for RendererError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			RendererError::Io { path, error } => {
+				write!(f, "Could not read {}: {}", path.display(), error)
+			}
+			RendererError::Image { path, error } => {
+				write!(f, "Could not load image {}: {}", path.display(), error)
+			}
+			RendererError::Font { path, error } => {
+				write!(f, "Could not load font {}: {}", path.display(), error)
+			}
+			RendererError::UnknownShaderType { path, name } => {
+				write!(f, "Unknown shader type {} in {}", name, path.display())
+			}
+			RendererError::IncludeCycle(files) => {
+				write!(f, "Include cycle: ")?;
+				for (i, file) in files.iter().enumerate() {
+					if i > 0 {
+						write!(f, " -> ")?;
+					}
+					write!(f, "{}", file.display())?;
+				}
+				Ok(())
+			}
+			RendererError::MalformedInclude { path, line } => {
+				write!(f, "Malformed #include at {}:{}", path.display(), line)
+			}
+			RendererError::InvalidStages { path, stages } => {
+				write!(
+					f,
+					"Invalid combination of shader stages in {}:",
+					path.display()
+				)?;
+				for type_ in stages {
+					write!(f, " {}", type_)?;
+				}
+				Ok(())
+			}
+			RendererError::Compile(logs) => {
+				for (i, (type_, log)) in logs.iter().enumerate() {
+					if i > 0 {
+						writeln!(f)?;
+					}
+					write!(f, "{} Shader Compilation Failiure:\n{}", type_, log)?;
+				}
+				Ok(())
+			}
+			RendererError::Link(log) => write!(f, "Shader Program Linking Failiure:\n{}", log),
+			RendererError::VertexLayoutMismatch { size, stride } => write!(
+				f,
+				"Vertex data of {} bytes is not a multiple of the layout stride {}",
+				size, stride,
+			),
+			RendererError::InvalidShaderHandle(handle) => {
+				write!(f, "Unknown shader handle {}", handle.id)
+			}
+			RendererError::InvalidMeshHandle(handle) => {
+				write!(f, "Unknown mesh handle {}", handle.id)
+			}
+			RendererError::DrawRangeOutOfBounds { mesh, range, len } => write!(
+				f,
+				"Draw range {:?} is outside of mesh {} with {} elements",
+				range, mesh.id, len
+			),
+			RendererError::InvalidInstanceBufferHandle(handle) => {
+				write!(f, "Unknown instance buffer handle {}", handle.id)
+			}
+			RendererError::InstanceCountOutOfBounds {
+				instances,
+				count,
+				len,
+			} => write!(
+				f,
+				"Drawing {} instances reads past the {} elements of instance buffer {}",
+				count, len, instances.id
+			),
+			RendererError::InvalidUniformBufferHandle(handle) => {
+				write!(f, "Unknown uniform buffer handle {}", handle.id)
+			}
+			RendererError::UniformBlockSizeMismatch {
+				name,
+				size,
+				expected,
+			} => write!(
+				f,
+				"Uniform block {} is {} bytes but {} were expected",
+				name, size, expected
+			),
+			RendererError::UniformBlockLayoutMismatch {
+				name,
+				offsets,
+				expected,
+			} => write!(
+				f,
+				"The fields of uniform block {} are at offsets {:?} but std140 puts them at {:?}",
+				name, offsets, expected
+			),
+			RendererError::InvalidTextureHandle(handle) => {
+				write!(f, "Unknown texture handle {}", handle.id)
+			}
+			RendererError::TextureDataMismatch { size, expected } => write!(
+				f,
+				"Texture data of {} bytes does not match the expected {}",
+				size, expected
+			),
+			RendererError::TextureRegionOutOfBounds {
+				offset,
+				size,
+				texture_size,
+			} => write!(
+				f,
+				"Texture region of {:?} at {:?} is outside of the {:?} texture",
+				size, offset, texture_size
+			),
+			RendererError::FontAtlasFull { texture, size } => write!(
+				f,
+				"The {}x{} glyph atlas texture {} is full, glyphs that don't fit are not drawn",
+				size, size, texture.id
+			),
+			RendererError::InvalidFramebufferHandle(handle) => {
+				write!(f, "Unknown framebuffer handle {}", handle.id)
+			}
+			RendererError::InvalidFramebufferAttachment { handle, index } => write!(
+				f,
+				"Framebuffer {} has no color attachment {}",
+				handle.id, index
+			),
+			RendererError::TextureOwnedByFramebuffer {
+				texture,
+				framebuffer,
+			} => write!(
+				f,
+				"Texture {} is attached to framebuffer {} and is deleted with it",
+				texture.id, framebuffer.id
+			),
+			RendererError::IncompleteFramebuffer(status) => {
+				write!(f, "Framebuffer is incomplete: status {:#x}", status)
+			}
+			RendererError::UnknownUniform { handle, name } => {
+				write!(f, "Shader {} has no active uniform {}", handle.id, name)
+			}
+			RendererError::UniformTypeMismatch {
+				handle,
+				name,
+				expected,
+			} => write!(
+				f,
+				"Uniform {} of shader {} expects a {}",
+				name, handle.id, expected
+			),
+		}
+	}
+}
+
+impl Error for RendererError {
+	fn source(&self) -> Option<&(dyn Error + 'static)> {
+		match self {
+			RendererError::Io { error, .. } => Some(error),
+			RendererError::Image { error, .. } => Some(error),
+			RendererError::Font { error, .. } => Some(error),
+			_ => None,
+		}
+	}
+}
@@ -0,0 +1,483 @@
+use super::{
+	error::RendererError,
+	renderer_2d::Rect,
+	texture::{SamplerState, TextureFilter, TextureFormat, TextureHandle, TextureWrap},
+	Renderer,
+};
+
+use rusttype::{point, Scale};
+use whm::Float;
+
+use std::{collections::HashMap, fs, path::Path};
+
+const TRUE_TYPE_ATLAS_SIZE: u32 = 1024;
+const BITMAP_ATLAS_SIZE: u32 = 128;
+
+// the bitmap font has 3x5 glyphs in 4x7 cells, lowercase letters are drawn as
+// uppercase ones. every glyph is 15 bits, one row of 3 per 3 bits from the top
+const BITMAP_CELL: (Float, Float) = (4.0, 7.0);
+const BITMAP_ASCENT: Float = 5.0;
+const BITMAP_GLYPHS: [u16; 69] = [
+	0x0000, 0x2482, 0x5a00, 0x5f7d, 0x3c9e, 0x42a1, 0x2aab, 0x2400, //   ! " # $ % & '
+	0x1491, 0x4494, 0x55d5, 0x05d0, 0x0014, 0x01c0, 0x0002, 0x12a4, // ( ) * + , - . /
+	0x7b6f, 0x2c97, 0x73e7, 0x72cf, 0x5bc9, 0x79cf, 0x79ef, 0x7252, // 0 1 2 3 4 5 6 7
+	0x7bef, 0x7bcf, 0x0410, 0x0414, 0x1511, 0x0e38, 0x4454, 0x72c2, // 8 9 : ; < = > ?
+	0x7b63, 0x7bed, 0x6bae, 0x3923, 0x6b6e, 0x79a7, 0x79a4, 0x396b, // @ A B C D E F G
+	0x5bed, 0x7497, 0x126a, 0x5bad, 0x4927, 0x5fed, 0x6b6d, 0x2b6a, // H I J K L M N O
+	0x6ba4, 0x2b73, 0x6bad, 0x388e, 0x7492, 0x5b6b, 0x5b52, 0x5bfd, // P Q R S T U V W
+	0x5aad, 0x5a92, 0x72a7, 0x6926, 0x4889, 0x324b, 0x2a00, 0x0007, // X Y Z [ \ ] ^ _
+	0x4400, 0x3593, 0x2492, 0x64d6, 0x0780, // ` { | } ~
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextAlign {
+	Left,
+	Center,
+	Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextOptions {
+	// in pixels
+	pub size: Float,
+	pub align: TextAlign,
+	// longer lines are broken at spaces, alignment is within this width if set
+	pub max_width: Option<Float>,
+}
+
+impl TextOptions {
+	pub fn new(size: Float) -> Self {
+		Self {
+			size,
+			align: TextAlign::Left,
+			max_width: None,
+		}
+	}
+	pub fn align(self, align: TextAlign) -> Self {
+		Self { align, ..self }
+	}
+	pub fn max_width(self, max_width: Float) -> Self {
+		Self {
+			max_width: Some(max_width),
+			..self
+		}
+	}
+}
+
+enum Source {
+	TrueType(rusttype::Font<'static>),
+	Bitmap,
+}
+
+// where a glyph goes relative to the pen on the baseline, in pixels
+#[derive(Clone, Copy)]
+pub(crate) struct Glyph {
+	pub offset: (Float, Float),
+	pub size: (Float, Float),
+	pub uv: Rect,
+}
+
+// glyphs are rasterized into the atlas the first time they're drawn at a size,
+// the ones that don't fit anymore are skipped and the first one is reported
+pub struct Font {
+	source: Source,
+	texture: TextureHandle,
+	atlas_size: u32,
+	cursor: (u32, u32),
+	row_height: u32,
+	// None for glyphs without pixels, like spaces
+	glyphs: HashMap<(char, u32), Option<Glyph>>,
+	// atlas regions not yet written to the texture
+	uploads: Vec<((u32, u32), (u32, u32), Vec<u8>)>,
+	full_reported: bool,
+}
+
+impl Font {
+	// ttf and otf
+	pub fn from_file(renderer: &mut dyn Renderer, path: &Path) -> Result<Self, RendererError> {
+		let bytes = fs::read(path).map_err(|error| RendererError::Io {
+			path: path.into(),
+			error,
+		})?;
+		let font = rusttype::Font::from_bytes(bytes).map_err(|error| RendererError::Font {
+			path: path.into(),
+			error,
+		})?;
+		Self::new(
+			renderer,
+			Source::TrueType(font),
+			TRUE_TYPE_ATLAS_SIZE,
+			TextureFilter::Linear,
+		)
+	}
+	// built in and crisp at multiples of 5 pixels, for debug text
+	pub fn bitmap(renderer: &mut dyn Renderer) -> Result<Self, RendererError> {
+		Self::new(
+			renderer,
+			Source::Bitmap,
+			BITMAP_ATLAS_SIZE,
+			TextureFilter::Nearest,
+		)
+	}
+	fn new(
+		renderer: &mut dyn Renderer,
+		source: Source,
+		atlas_size: u32,
+		filter: TextureFilter,
+	) -> Result<Self, RendererError> {
+		let pixels = vec![0; atlas_size as usize * atlas_size as usize * 4];
+		let texture = renderer.create_texture(
+			(atlas_size, atlas_size),
+			TextureFormat::Rgba8,
+			Some(&pixels),
+			&SamplerState {
+				min_filter: filter,
+				mag_filter: filter,
+				wrap_s: TextureWrap::ClampToEdge,
+				wrap_t: TextureWrap::ClampToEdge,
+				mipmaps: false,
+				..Default::default()
+			},
+		)?;
+		Ok(Self {
+			source,
+			texture,
+			atlas_size,
+			cursor: (1, 1),
+			row_height: 0,
+			glyphs: HashMap::new(),
+			uploads: vec![],
+			full_reported: false,
+		})
+	}
+	pub fn delete(self, renderer: &mut dyn Renderer) -> Result<(), RendererError> {
+		renderer.delete_texture(self.texture)
+	}
+	pub fn texture(&self) -> TextureHandle {
+		self.texture
+	}
+	// distance between the baselines of two lines
+	pub fn line_height(&self, size: Float) -> Float {
+		let size = self.pixel_size(size);
+		match &self.source {
+			Source::TrueType(font) => {
+				let metrics = font.v_metrics(Scale::uniform(size));
+				metrics.ascent - metrics.descent + metrics.line_gap
+			}
+			Source::Bitmap => BITMAP_CELL.1 * size / BITMAP_ASCENT,
+		}
+	}
+	// width and height of the laid out text
+	pub fn measure(&self, text: &str, options: &TextOptions) -> (Float, Float) {
+		self.layout(text, options).1
+	}
+	// every glyph with the position of the pen on its baseline relative to the
+	// top left of the text, and the size of the text
+	pub(crate) fn layout(
+		&self,
+		text: &str,
+		options: &TextOptions,
+	) -> (Vec<(char, (Float, Float))>, (Float, Float)) {
+		let size = self.pixel_size(options.size);
+		let ascent = self.ascent(size);
+		let line_height = self.line_height(size);
+
+		let lines = self.break_lines(text, size, options.max_width);
+		let widths = lines
+			.iter()
+			.map(|line| self.line_width(line, size))
+			.collect::<Vec<_>>();
+		let width = widths.iter().copied().fold(0.0, Float::max);
+		let box_width = options.max_width.unwrap_or(width);
+
+		let mut glyphs = vec![];
+		for (i, (line, line_width)) in lines.iter().zip(&widths).enumerate() {
+			let mut x = match options.align {
+				TextAlign::Left => 0.0,
+				TextAlign::Center => (box_width - line_width) / 2.0,
+				TextAlign::Right => box_width - line_width,
+			};
+			let y = ascent + i as Float * line_height;
+			let mut previous = None;
+			for c in line.chars() {
+				if let Some(previous) = previous {
+					x += self.kerning(previous, c, size);
+				}
+				glyphs.push((c, (x, y)));
+				x += self.advance(c, size);
+				previous = Some(c);
+			}
+		}
+
+		(glyphs, (width, lines.len() as Float * line_height))
+	}
+	// glyphs that didn't fit aren't cached, the atlas is only reported full once
+	pub(crate) fn glyph(&mut self, c: char, size: Float) -> Result<Option<Glyph>, RendererError> {
+		let size = self.pixel_size(size);
+		let (key, scale) = match self.source {
+			Source::TrueType(_) => ((c, size as u32), 1.0),
+			Source::Bitmap => ((c.to_ascii_uppercase(), 0), size / BITMAP_ASCENT),
+		};
+		if !self.glyphs.contains_key(&key) {
+			match self.rasterize(key.0, size) {
+				Ok(glyph) => {
+					self.glyphs.insert(key, glyph);
+				}
+				Err(_) if self.full_reported => return Ok(None),
+				Err(error) => {
+					self.full_reported = true;
+					return Err(error);
+				}
+			}
+		}
+
+		Ok(self.glyphs[&key].map(|glyph| Glyph {
+			offset: (glyph.offset.0 * scale, glyph.offset.1 * scale),
+			size: (glyph.size.0 * scale, glyph.size.1 * scale),
+			uv: glyph.uv,
+		}))
+	}
+	pub(crate) fn take_uploads(&mut self) -> Vec<((u32, u32), (u32, u32), Vec<u8>)> {
+		std::mem::replace(&mut self.uploads, vec![])
+	}
+	fn pixel_size(&self, size: Float) -> Float {
+		match self.source {
+			// rusttype positions glyphs on whole pixels anyway
+			Source::TrueType(_) => size.round().max(1.0),
+			Source::Bitmap => size,
+		}
+	}
+	fn ascent(&self, size: Float) -> Float {
+		match &self.source {
+			Source::TrueType(font) => font.v_metrics(Scale::uniform(size)).ascent,
+			Source::Bitmap => size,
+		}
+	}
+	fn advance(&self, c: char, size: Float) -> Float {
+		match &self.source {
+			Source::TrueType(font) => {
+				font.glyph(c)
+					.scaled(Scale::uniform(size))
+					.h_metrics()
+					.advance_width
+			}
+			Source::Bitmap => BITMAP_CELL.0 * size / BITMAP_ASCENT,
+		}
+	}
+	fn kerning(&self, first: char, second: char, size: Float) -> Float {
+		match &self.source {
+			Source::TrueType(font) => font.pair_kerning(Scale::uniform(size), first, second),
+			Source::Bitmap => 0.0,
+		}
+	}
+	fn line_width(&self, line: &str, size: Float) -> Float {
+		let mut width = 0.0;
+		let mut previous = None;
+		for c in line.chars() {
+			if let Some(previous) = previous {
+				width += self.kerning(previous, c, size);
+			}
+			width += self.advance(c, size);
+			previous = Some(c);
+		}
+		width
+	}
+	fn break_lines(&self, text: &str, size: Float, max_width: Option<Float>) -> Vec<String> {
+		let max_width = match max_width {
+			Some(max_width) => max_width,
+			None => return text.lines().map(String::from).collect(),
+		};
+
+		let mut lines = vec![];
+		for paragraph in text.lines() {
+			let mut line = String::new();
+			for word in paragraph.split(' ') {
+				let candidate = if line.is_empty() {
+					word.to_string()
+				} else {
+					format!("{} {}", line, word)
+				};
+				// words wider than max_width get a line of their own
+				if !line.is_empty() && self.line_width(&candidate, size) > max_width {
+					lines.push(std::mem::replace(&mut line, word.to_string()));
+				} else {
+					line = candidate;
+				}
+			}
+			lines.push(line);
+		}
+		lines
+	}
+	// None for glyphs without pixels
+	fn rasterize(&mut self, c: char, size: Float) -> Result<Option<Glyph>, RendererError> {
+		let (width, height, offset, coverage) = match &self.source {
+			Source::TrueType(font) => {
+				let glyph = font
+					.glyph(c)
+					.scaled(Scale::uniform(size))
+					.positioned(point(0.0, 0.0));
+				let bounds = match glyph.pixel_bounding_box() {
+					Some(bounds) => bounds,
+					None => return Ok(None),
+				};
+				let (width, height) = (bounds.width() as u32, bounds.height() as u32);
+				let mut coverage = vec![0; width as usize * height as usize];
+				glyph.draw(|x, y, v| {
+					coverage[(y * width + x) as usize] = (v * 255.0) as u8;
+				});
+				(
+					width,
+					height,
+					(bounds.min.x as Float, bounds.min.y as Float),
+					coverage,
+				)
+			}
+			Source::Bitmap => {
+				let bits = bitmap_glyph(c);
+				if bits == 0 {
+					return Ok(None);
+				}
+				let coverage = (0..15)
+					.map(|i| if bits & (1 << (14 - i)) != 0 { 255 } else { 0 })
+					.collect();
+				(3, 5, (0.0, -BITMAP_ASCENT), coverage)
+			}
+		};
+
+		let position = self
+			.allocate(width, height)
+			.ok_or(RendererError::FontAtlasFull {
+				texture: self.texture,
+				size: self.atlas_size,
+			})?;
+		// white with the coverage as alpha, rows go from the bottom up
+		let mut pixels = Vec::with_capacity(width as usize * height as usize * 4);
+		for row in (0..height).rev() {
+			for x in 0..width {
+				let alpha = coverage[(row * width + x) as usize];
+				pixels.extend_from_slice(&[255, 255, 255, alpha]);
+			}
+		}
+		self.uploads.push((position, (width, height), pixels));
+
+		let atlas_size = self.atlas_size as Float;
+		Ok(Some(Glyph {
+			offset,
+			size: (width as Float, height as Float),
+			uv: Rect::new(
+				position.0 as Float / atlas_size,
+				position.1 as Float / atlas_size,
+				width as Float / atlas_size,
+				height as Float / atlas_size,
+			),
+		}))
+	}
+	// rows of glyphs with a pixel of padding around every glyph
+	fn allocate(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+		if self.cursor.0 + width + 1 > self.atlas_size {
+			self.cursor = (1, self.cursor.1 + self.row_height + 1);
+			self.row_height = 0;
+		}
+		if self.cursor.0 + width + 1 > self.atlas_size
+			|| self.cursor.1 + height + 1 > self.atlas_size
+		{
+			return None;
+		}
+
+		let position = self.cursor;
+		self.cursor.0 += width + 1;
+		self.row_height = self.row_height.max(height);
+		Some(position)
+	}
+}
+
+fn bitmap_glyph(c: char) -> u16 {
+	let index = match c {
+		' '..='`' => c as usize - ' ' as usize,
+		'{'..='~' => c as usize - '{' as usize + 65,
+		_ => '?' as usize - ' ' as usize,
+	};
+	BITMAP_GLYPHS[index]
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{DrawRange, RecordingRenderer, RenderCommand, Renderer2D};
+
+	#[test]
+	fn bitmap_text() {
+		let renderer = RecordingRenderer::new();
+		let log = renderer.log();
+		let mut renderer: Box<dyn Renderer> = box renderer;
+		let mut font = Font::bitmap(renderer.as_mut()).unwrap();
+
+		let options = TextOptions::new(10.0);
+		assert_eq!(font.measure("AB\nC", &options), (16.0, 28.0));
+		assert_eq!(
+			font.measure("AB CD", &options.max_width(30.0)),
+			(16.0, 28.0)
+		);
+
+		let mut renderer_2d = Renderer2D::new();
+		renderer_2d.draw_text(&mut font, "a a", (0.0, 0.0), &options, [1.0; 4]);
+		log.take_commands();
+		renderer_2d.flush(renderer.as_mut()).unwrap();
+
+		let commands = log.take_commands();
+		let uploads = commands
+			.iter()
+			.filter(|c| match c {
+				RenderCommand::UpdateTextureRegion { size, .. } => *size == (3, 5),
+				_ => false,
+			})
+			.count();
+		assert_eq!(uploads, 1);
+		assert!(commands.iter().any(|c| match c {
+			RenderCommand::Render { params, .. } => {
+				params.range
+					== DrawRange::Indices {
+						first: 0,
+						count: 12,
+						base_vertex: 0,
+					}
+			}
+			_ => false,
+		}));
+
+		renderer_2d.delete(renderer.as_mut()).unwrap();
+		font.delete(renderer.as_mut()).unwrap();
+		assert!(log.live_textures().is_empty());
+	}
+
+	#[test]
+	fn full_atlas() {
+		let renderer = RecordingRenderer::new();
+		let log = renderer.log();
+		let mut renderer: Box<dyn Renderer> = box renderer;
+		// room for a single 3x5 glyph
+		let mut font =
+			Font::new(renderer.as_mut(), Source::Bitmap, 8, TextureFilter::Nearest).unwrap();
+		let options = TextOptions::new(5.0);
+
+		let mut renderer_2d = Renderer2D::new();
+		renderer_2d.draw_text(&mut font, "AB", (0.0, 0.0), &options, [1.0; 4]);
+		match renderer_2d.flush(renderer.as_mut()) {
+			Err(RendererError::FontAtlasFull { size: 8, .. }) => {}
+			result => panic!("expected a full atlas, got {:?}", result.err()),
+		}
+		assert!(log.take_commands().iter().any(|c| match c {
+			RenderCommand::Render { .. } => true,
+			_ => false,
+		}));
+
+		// reported once, and the glyph that didn't fit isn't cached
+		renderer_2d.draw_text(&mut font, "BC", (0.0, 0.0), &options, [1.0; 4]);
+		renderer_2d.flush(renderer.as_mut()).unwrap();
+		assert!(font.glyph('A', 5.0).unwrap().is_some());
+		assert!(!font.glyphs.contains_key(&('B', 0)));
+
+		renderer_2d.delete(renderer.as_mut()).unwrap();
+		font.delete(renderer.as_mut()).unwrap();
+	}
+}
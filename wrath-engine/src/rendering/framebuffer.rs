@@ -0,0 +1,110 @@
+use super::texture::{SamplerState, TextureFormat, TextureWrap};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FramebufferHandle {
+	pub id: u32,
+}
+
+impl FramebufferHandle {
+	pub fn new(id: u32) -> Self {
+		Self { id }
+	}
+	pub fn none() -> Self {
+		Self { id: 0 }
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepthFormat {
+	Depth24,
+	Depth32F,
+	Depth24Stencil8,
+}
+
+impl DepthFormat {
+	pub fn has_stencil(&self) -> bool {
+		match self {
+			DepthFormat::Depth24Stencil8 => true,
+			_ => false,
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FramebufferSpec {
+	pub size: (u32, u32),
+	// one texture per format, written to by out location 0, 1, ...
+	pub color: Vec<TextureFormat>,
+	// depth and stencil can be written but not sampled
+	pub depth: Option<DepthFormat>,
+	// more than 1 renders multisampled and resolves into the color textures
+	// when another framebuffer is bound
+	pub samples: u32,
+	pub sampler: SamplerState,
+}
+
+impl FramebufferSpec {
+	// a single rgba8 color texture with depth and stencil
+	pub fn new(size: (u32, u32)) -> Self {
+		Self {
+			size,
+			color: vec![TextureFormat::Rgba8],
+			depth: Some(DepthFormat::Depth24Stencil8),
+			samples: 1,
+			sampler: SamplerState {
+				wrap_s: TextureWrap::ClampToEdge,
+				wrap_t: TextureWrap::ClampToEdge,
+				mipmaps: false,
+				..Default::default()
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{RecordingRenderer, Renderer, RendererError, TextureFormat};
+
+	#[test]
+	fn framebuffer() {
+		let recording = RecordingRenderer::new();
+		let log = recording.log();
+		let mut renderer: Box<dyn Renderer> = box recording;
+		let framebuffer = renderer
+			.create_framebuffer(&FramebufferSpec {
+				color: vec![TextureFormat::Rgba8, TextureFormat::R32UI],
+				samples: 4,
+				..FramebufferSpec::new((64, 32))
+			})
+			.unwrap();
+
+		let ids = renderer.framebuffer_texture(framebuffer, 1).unwrap();
+		assert_eq!(renderer.texture_size(ids).unwrap(), (64, 32));
+		assert!(renderer.framebuffer_texture(framebuffer, 2).is_err());
+		assert_eq!(
+			renderer
+				.read_framebuffer(framebuffer, 1, (10, 10), (2, 2))
+				.unwrap()
+				.len(),
+			16
+		);
+		assert!(renderer
+			.read_framebuffer(framebuffer, 1, (63, 0), (2, 1))
+			.is_err());
+
+		// attachments only go away with their framebuffer
+		match renderer.delete_texture(ids) {
+			Err(RendererError::TextureOwnedByFramebuffer { texture, .. }) => {
+				assert_eq!(texture, ids)
+			}
+			result => panic!("expected the texture to be owned, got {:?}", result),
+		}
+		renderer.resize_framebuffer(framebuffer, (128, 64)).unwrap();
+		assert_eq!(renderer.texture_size(ids).unwrap(), (128, 64));
+
+		renderer.delete_framebuffer(framebuffer).unwrap();
+		assert!(log.live_framebuffers().is_empty());
+		assert!(log.live_textures().is_empty());
+	}
+}
@@ -1,39 +1,65 @@
-use whm::{
-	Float,
-	Vector3,
-	Vector4,
-};
+use super::error::RendererError;
 
-pub struct Vertices(Vec<Float>);
+use whm::Float;
+
+use std::mem::size_of;
+
+// raw vertex data, so compact formats can be mixed with floats
+pub struct Vertices(Vec<u8>);
 
 impl Vertices {
 	pub fn new(v: Vec<Float>) -> Self {
-		Self(v)
+		let bytes = unsafe {
+			std::slice::from_raw_parts(
+				v.as_ptr() as *const u8,
+				std::mem::size_of_val(v.as_slice()),
+			)
+		};
+		Self(bytes.to_vec())
+	}
+	pub fn from_bytes(bytes: Vec<u8>) -> Self {
+		Self(bytes)
 	}
 	pub fn size(&self) -> usize {
-		std::mem::size_of_val(self.0.as_slice())
+		self.0.len()
 	}
-	pub fn as_ptr(&self) -> *const Float {
+	pub fn as_ptr(&self) -> *const u8 {
 		self.0.as_ptr()
 	}
 }
 
 impl Into<Vertices> for Vec<Float> {
 	fn into(self) -> Vertices {
-		Vertices(self)
+		Vertices::new(self)
 	}
 }
 
+impl Into<Vertices> for Vec<u8> {
+	fn into(self) -> Vertices {
+		Vertices::from_bytes(self)
+	}
+}
+
+#[derive(Debug, Clone)]
 pub struct BufferLayout {
 	pub types: Vec<BufferElement>,
 	pub counts: Vec<usize>,
 	pub offsets: Vec<usize>,
 	pub stride: usize,
 	pub len: usize,
+	// 0 advances the attributes every vertex, n every n instances
+	pub divisor: u32,
 }
 
 impl BufferLayout {
 	pub fn new(elements: &[BufferElement]) -> Self {
+		Self::with_divisor(elements, 0)
+	}
+	// for instance buffers, one element per instance
+	pub fn per_instance(elements: &[BufferElement]) -> Self {
+		Self::with_divisor(elements, 1)
+	}
+	pub fn with_divisor(elements: &[BufferElement], divisor: u32) -> Self {
 		let mut offset = 0;
 		let mut types = Vec::with_capacity(elements.len());
 		let mut counts = Vec::with_capacity(elements.len());
@@ -50,27 +76,88 @@ impl BufferLayout {
 			offsets,
 			stride: offset,
 			len: elements.len(),
+			divisor,
 		}
 	}
 }
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BufferElement {
+	Float,
+	Vector2,
 	Vector3,
 	Vector4,
+	// takes up four attribute slots, one per column
+	Matrix4,
+	// read as floats in 0..1 or -1..1
+	U8x4Norm,
+	I8x4Norm,
+	U16x2Norm,
+	I16x2Norm,
+	// read as integers, e.g. bone indices
+	U8x4,
+	I8x4,
+	U16x2,
+	I16x2,
+	U32,
+	I32,
 }
 
 impl BufferElement {
+	// components per attribute slot
 	pub fn count(&self) -> usize {
 		match self {
-			BufferElement::Vector3 => Vector3::len(),
-			BufferElement::Vector4 => Vector4::len(),
+			BufferElement::Float => 1,
+			BufferElement::Vector2 => 2,
+			BufferElement::Vector3 => 3,
+			BufferElement::Vector4 => 4,
+			BufferElement::Matrix4 => 4,
+			BufferElement::U8x4Norm | BufferElement::I8x4Norm => 4,
+			BufferElement::U16x2Norm | BufferElement::I16x2Norm => 2,
+			BufferElement::U8x4 | BufferElement::I8x4 => 4,
+			BufferElement::U16x2 | BufferElement::I16x2 => 2,
+			BufferElement::U32 | BufferElement::I32 => 1,
 		}
 	}
 	pub fn size(&self) -> usize {
 		match self {
-			BufferElement::Vector3 => std::mem::size_of::<Vector3>(),
-			BufferElement::Vector4 => std::mem::size_of::<Vector4>(),
+			BufferElement::Float => size_of::<Float>(),
+			BufferElement::Vector2 => size_of::<Float>() * 2,
+			BufferElement::Vector3 => size_of::<Float>() * 3,
+			BufferElement::Vector4 => size_of::<Float>() * 4,
+			BufferElement::Matrix4 => size_of::<Float>() * 16,
+			BufferElement::U8x4Norm | BufferElement::U8x4 => size_of::<u8>() * 4,
+			BufferElement::I8x4Norm | BufferElement::I8x4 => size_of::<i8>() * 4,
+			BufferElement::U16x2Norm | BufferElement::U16x2 => size_of::<u16>() * 2,
+			BufferElement::I16x2Norm | BufferElement::I16x2 => size_of::<i16>() * 2,
+			BufferElement::U32 => size_of::<u32>(),
+			BufferElement::I32 => size_of::<i32>(),
+		}
+	}
+	pub fn slots(&self) -> usize {
+		match self {
+			BufferElement::Matrix4 => 4,
+			_ => 1,
+		}
+	}
+	pub fn normalized(&self) -> bool {
+		match self {
+			BufferElement::U8x4Norm
+			| BufferElement::I8x4Norm
+			| BufferElement::U16x2Norm
+			| BufferElement::I16x2Norm => true,
+			_ => false,
+		}
+	}
+	pub fn integer(&self) -> bool {
+		match self {
+			BufferElement::U8x4
+			| BufferElement::I8x4
+			| BufferElement::U16x2
+			| BufferElement::I16x2
+			| BufferElement::U32
+			| BufferElement::I32 => true,
+			_ => false,
 		}
 	}
 }
@@ -123,7 +210,137 @@ impl Into<Indices> for Vec<u32> {
 	}
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+// how often the mesh is expected to change, updating a static mesh still
+// works but may be slow
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BufferUsage {
+	// uploaded once
+	Static,
+	// updated now and then
+	Dynamic,
+	// rewritten every frame
+	Stream,
+}
+
+impl Default for BufferUsage {
+	fn default() -> Self {
+		BufferUsage::Static
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Primitive {
+	Points,
+	Lines,
+	LineStrip,
+	Triangles,
+	TriangleStrip,
+	TriangleFan,
+}
+
+impl Default for Primitive {
+	fn default() -> Self {
+		Primitive::Triangles
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrawRange {
+	// every index of the mesh
+	All,
+	// count indices from first on, base_vertex is added to each of them
+	Indices {
+		first: usize,
+		count: usize,
+		base_vertex: i32,
+	},
+	// count vertices from first on, without going through the indices
+	Vertices {
+		first: usize,
+		count: usize,
+	},
+}
+
+impl Default for DrawRange {
+	fn default() -> Self {
+		DrawRange::All
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DrawParams {
+	pub primitive: Primitive,
+	pub range: DrawRange,
+}
+
+impl DrawParams {
+	pub fn new(primitive: Primitive, range: DrawRange) -> Self {
+		Self {
+			primitive,
+			range,
+		}
+	}
+	pub(crate) fn check(
+		&self,
+		mesh: MeshHandle,
+		index_count: usize,
+		vertex_count: usize,
+	) -> Result<(), RendererError> {
+		let (end, len) = match self.range {
+			DrawRange::All => return Ok(()),
+			DrawRange::Indices { first, count, .. } => (first + count, index_count),
+			DrawRange::Vertices { first, count } => (first + count, vertex_count),
+		};
+		if end > len {
+			Err(RendererError::DrawRangeOutOfBounds {
+				mesh,
+				range: self.range,
+				len,
+			})
+		} else {
+			Ok(())
+		}
+	}
+}
+
+// drawing count instances reads one element every divisor instances
+pub(crate) fn check_instances(
+	instances: InstanceBufferHandle,
+	len: usize,
+	divisor: u32,
+	count: usize,
+) -> Result<(), RendererError> {
+	let divisor = divisor as usize;
+	if divisor != 0 && (count + divisor - 1) / divisor > len {
+		Err(RendererError::InstanceCountOutOfBounds {
+			instances,
+			count,
+			len,
+		})
+	} else {
+		Ok(())
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InstanceBufferHandle {
+	pub id: u32
+}
+
+impl InstanceBufferHandle {
+	pub fn new(id: u32) -> Self {
+		Self {
+			id
+		}
+	}
+	pub fn none() -> Self {
+		Self {
+			id: 0
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct MeshHandle {
 	pub id: u32
 }
@@ -140,3 +357,183 @@ impl MeshHandle {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{RecordingRenderer, RenderCommand, Renderer, ShaderHandle};
+
+	use std::path::Path;
+
+	#[test]
+	fn buffer_layout() {
+		let layout = BufferLayout::new(&[
+			BufferElement::Vector3,
+			BufferElement::I16x2Norm,
+			BufferElement::U8x4Norm,
+			BufferElement::Matrix4,
+		]);
+		assert_eq!(layout.offsets, vec![0, 12, 16, 20]);
+		assert_eq!(layout.stride, 84);
+		assert_eq!(layout.counts, vec![3, 2, 4, 4]);
+
+		let layout = BufferLayout::new(&[
+			BufferElement::I8x4,
+			BufferElement::U16x2Norm,
+			BufferElement::I16x2,
+		]);
+		assert_eq!(layout.offsets, vec![0, 4, 8]);
+		assert_eq!(layout.stride, 12);
+		assert!(BufferElement::U16x2Norm.normalized() && !BufferElement::U16x2Norm.integer());
+		assert!(BufferElement::I8x4.integer() && !BufferElement::I8x4.normalized());
+	}
+
+	#[test]
+	fn mesh_updates() {
+		let recording = RecordingRenderer::new();
+		let log = recording.log();
+		let mut renderer: Box<dyn Renderer> = box recording;
+		let mesh = renderer
+			.create_mesh_from_layout(
+				&Vertices::new(vec![0.0; 6]),
+				&BufferLayout::new(&[BufferElement::Vector3]),
+				&Indices::U8(vec![0, 1]),
+				BufferUsage::Stream,
+			)
+			.unwrap();
+
+		assert!(renderer
+			.update_vertices(mesh, 2, &Vertices::new(vec![1.0; 6]))
+			.is_ok());
+		assert!(renderer
+			.update_vertices(mesh, 0, &Vertices::new(vec![1.0; 4]))
+			.is_err());
+		assert!(renderer
+			.update_indices(mesh, &Indices::U16(vec![0, 1, 2, 3]))
+			.is_ok());
+
+		match log.commands().last() {
+			Some(RenderCommand::UpdateIndices { index_count, .. }) => assert_eq!(*index_count, 4),
+			_ => panic!("expected index update"),
+		}
+
+		let lines = DrawParams::new(
+			Primitive::Lines,
+			DrawRange::Indices {
+				first: 2,
+				count: 2,
+				base_vertex: 0,
+			},
+		);
+		assert!(lines.check(mesh, 4, 4).is_ok());
+		let points = DrawParams::new(
+			Primitive::Points,
+			DrawRange::Vertices { first: 3, count: 2 },
+		);
+		assert!(points.check(mesh, 4, 4).is_err());
+
+		// a smaller upload to the start of a stream buffer replaces its contents
+		let shader = renderer.create_shader(Path::new("test.glsl")).unwrap();
+		renderer
+			.update_vertices(mesh, 0, &Vertices::new(vec![1.0; 3]))
+			.unwrap();
+		let vertices = DrawParams::new(
+			Primitive::Points,
+			DrawRange::Vertices { first: 0, count: 2 },
+		);
+		log.take_errors();
+		renderer.render_with(mesh, shader, &vertices);
+		assert_eq!(log.take_errors().len(), 1);
+	}
+
+	#[test]
+	fn instanced_rendering() {
+		let recording = RecordingRenderer::new();
+		let log = recording.log();
+		let mut renderer: Box<dyn Renderer> = box recording;
+		let shader = renderer.create_shader(Path::new("test.glsl")).unwrap();
+		let mesh = renderer
+			.create_mesh_from_layout(
+				&Vertices::new(vec![0.0; 9]),
+				&BufferLayout::new(&[BufferElement::Vector3]),
+				&Indices::U8(vec![0, 1, 2]),
+				BufferUsage::Static,
+			)
+			.unwrap();
+		let instances = renderer
+			.create_instance_buffer(
+				&Vertices::new(vec![0.0; 6]),
+				&BufferLayout::per_instance(&[BufferElement::Vector2]),
+				BufferUsage::Dynamic,
+			)
+			.unwrap();
+
+		renderer.render_instanced(mesh, shader, instances, 3);
+		assert_eq!(
+			log.commands().last(),
+			Some(&RenderCommand::RenderInstanced {
+				mesh,
+				shader,
+				instances,
+				count: 3,
+			})
+		);
+		assert!(check_instances(instances, 3, 1, 4).is_err());
+		assert!(check_instances(instances, 3, 2, 6).is_ok());
+
+		renderer.delete_instance_buffer(instances).unwrap();
+		assert!(log.live_instance_buffers().is_empty());
+	}
+
+	#[derive(Clone, Copy, crate::Vertex)]
+	#[repr(C)]
+	struct TestInstance {
+		offset: [f32; 2],
+	}
+
+	// what an overlay does every frame with the renderer it's given
+	fn draw_instances(
+		renderer: &mut dyn Renderer,
+		mesh: MeshHandle,
+		shader: ShaderHandle,
+		instances: InstanceBufferHandle,
+		offsets: &[TestInstance],
+	) {
+		renderer
+			.update_instance_buffer_from_slice(instances, 0, offsets)
+			.unwrap();
+		renderer.render_instanced(mesh, shader, instances, offsets.len());
+	}
+
+	#[test]
+	fn typed_instances() {
+		let recording = RecordingRenderer::new();
+		let log = recording.log();
+		let mut renderer: Box<dyn Renderer> = box recording;
+		let shader = renderer.create_shader(Path::new("test.glsl")).unwrap();
+		let mesh = renderer
+			.create_mesh_from_layout(
+				&Vertices::new(vec![0.0; 9]),
+				&BufferLayout::new(&[BufferElement::Vector3]),
+				&Indices::U8(vec![0, 1, 2]),
+				BufferUsage::Static,
+			)
+			.unwrap();
+		let instances = renderer
+			.create_instance_buffer_from_slice::<TestInstance>(&[], BufferUsage::Stream)
+			.unwrap();
+
+		let offsets = [TestInstance { offset: [1.0, 2.0] }; 5];
+		draw_instances(renderer.as_mut(), mesh, shader, instances, &offsets);
+		assert_eq!(
+			log.commands().last(),
+			Some(&RenderCommand::RenderInstanced {
+				mesh,
+				shader,
+				instances,
+				count: 5,
+			})
+		);
+		assert!(log.take_errors().is_empty());
+	}
+}
@@ -0,0 +1,345 @@
+use super::{error::RendererError, shader::ShaderType};
+
+use std::{
+	fs,
+	path::{Path, PathBuf},
+};
+
+// name in #type sections and file name in the directory form
+const STAGES: [(ShaderType, &str, &str); 6] = [
+	(ShaderType::Vertex, "vertex", "vertex.glsl"),
+	(ShaderType::Fragment, "fragment", "fragment.glsl"),
+	(ShaderType::Geometry, "geometry", "geometry.glsl"),
+	(ShaderType::TessControl, "tess_control", "tess_control.glsl"),
+	(
+		ShaderType::TessEvaluation,
+		"tess_evaluation",
+		"tess_evaluation.glsl",
+	),
+	(ShaderType::Compute, "compute", "compute.glsl"),
+];
+
+pub struct ShaderSource {
+	pub files: Vec<PathBuf>,
+	pub stages: Vec<StageSource>,
+}
+
+pub struct StageSource {
+	pub type_: ShaderType,
+	pub source: String,
+	// (file index, line number) of every line in source, None for injected lines
+	origins: Vec<Option<(usize, usize)>>,
+}
+
+impl ShaderSource {
+	// rewrites "0:12(3): error" (mesa), "0(12) : error" (nvidia) and
+	// "ERROR: 0:12: error" (amd) so they point to the original file and line
+	pub fn map_log(&self, type_: ShaderType, log: &str) -> String {
+		let stage = match self.stages.iter().find(|s| s.type_ == type_) {
+			Some(stage) => stage,
+			None => return log.into(),
+		};
+
+		let mut mapped = String::new();
+		for line in log.lines() {
+			match self.map_log_line(stage, line) {
+				Some(line) => mapped.push_str(&line),
+				None => mapped.push_str(line),
+			}
+			mapped.push('\n');
+		}
+		mapped
+	}
+	fn map_log_line(&self, stage: &StageSource, line: &str) -> Option<String> {
+		let start = line.find(|c: char| c.is_ascii_digit())?;
+		if start != 0 && !line[..start].ends_with(": ") {
+			return None;
+		}
+
+		let rest = &line[start..];
+		let source_end = rest.find(|c: char| !c.is_ascii_digit())?;
+		let (close, rest) = match rest[source_end..].chars().next()? {
+			':' => (false, &rest[source_end + 1..]),
+			'(' => (true, &rest[source_end + 1..]),
+			_ => return None,
+		};
+		let line_end = rest.find(|c: char| !c.is_ascii_digit())?;
+		let line_number = rest[..line_end].parse::<usize>().ok()?;
+		let rest = &rest[line_end..];
+		let rest = if close {
+			if !rest.starts_with(')') {
+				return None;
+			}
+			&rest[1..]
+		} else {
+			rest
+		};
+
+		let (file, original_line) = (*stage.origins.get(line_number.checked_sub(1)?)?)?;
+
+		Some(format!(
+			"{}{}:{}{}",
+			&line[..start],
+			self.files[file].display(),
+			original_line,
+			rest
+		))
+	}
+}
+
+pub fn preprocess(
+	path: &Path,
+	defines: &[(String, String)],
+) -> Result<ShaderSource, RendererError> {
+	let mut files = vec![];
+	let mut stages = vec![];
+
+	if path.is_dir() {
+		for (type_, _, name) in &STAGES {
+			let file = path.join(name);
+			if !file.exists() {
+				continue;
+			}
+			let source = read(&file)?;
+			let mut lines = vec![];
+			expand(
+				&mut files,
+				&mut vec![],
+				&file,
+				source.lines().enumerate(),
+				&mut lines,
+			)?;
+			stages.push(finish_stage(*type_, lines, defines));
+		}
+	} else {
+		let source = read(path)?;
+
+		let mut sections: Vec<(ShaderType, Vec<(usize, &str)>)> = vec![];
+		let mut current = None;
+		for (i, line) in source.lines().enumerate() {
+			if line.starts_with("#type") {
+				let name = line[5..].trim();
+				let type_ = match STAGES.iter().find(|(_, n, _)| *n == name) {
+					Some((type_, _, _)) => *type_,
+					None => {
+						return Err(RendererError::UnknownShaderType {
+							path: path.into(),
+							name: name.into(),
+						})
+					}
+				};
+				current = match sections.iter().position(|(t, _)| *t == type_) {
+					Some(index) => Some(index),
+					None => {
+						sections.push((type_, vec![]));
+						Some(sections.len() - 1)
+					}
+				};
+			} else if let Some(index) = current {
+				sections[index].1.push((i, line));
+			}
+		}
+
+		for (type_, section) in sections {
+			let mut lines = vec![];
+			expand(
+				&mut files,
+				&mut vec![],
+				path,
+				section.into_iter(),
+				&mut lines,
+			)?;
+			stages.push(finish_stage(type_, lines, defines));
+		}
+	}
+
+	let types = stages.iter().map(|s| s.type_).collect::<Vec<_>>();
+	if !valid_stages(&types) {
+		return Err(RendererError::InvalidStages {
+			path: path.into(),
+			stages: types,
+		});
+	}
+
+	Ok(ShaderSource { files, stages })
+}
+
+pub fn stage_files(path: &Path) -> Vec<PathBuf> {
+	if path.is_dir() {
+		STAGES
+			.iter()
+			.map(|(_, _, name)| path.join(name))
+			.filter(|file| file.exists())
+			.collect()
+	} else {
+		vec![path.into()]
+	}
+}
+
+// either a lone compute stage or a vertex stage with optional fragment,
+// geometry and tessellation stages, where control needs evaluation
+fn valid_stages(types: &[ShaderType]) -> bool {
+	let has = |type_| types.contains(&type_);
+	if has(ShaderType::Compute) {
+		types.len() == 1
+	} else {
+		has(ShaderType::Vertex)
+			&& (!has(ShaderType::TessControl) || has(ShaderType::TessEvaluation))
+	}
+}
+
+type Line = (String, Option<(usize, usize)>);
+
+fn expand<'a>(
+	files: &mut Vec<PathBuf>,
+	stack: &mut Vec<PathBuf>,
+	path: &Path,
+	source: impl Iterator<Item = (usize, &'a str)>,
+	lines: &mut Vec<Line>,
+) -> Result<(), RendererError> {
+	let canonical = fs::canonicalize(path).unwrap_or_else(|_| path.into());
+	if stack.contains(&canonical) {
+		let mut cycle = stack.clone();
+		cycle.push(canonical);
+		return Err(RendererError::IncludeCycle(cycle));
+	}
+	stack.push(canonical);
+
+	let index = file_index(files, path);
+	for (i, line) in source {
+		let trimmed = line.trim_start();
+		if trimmed.starts_with("#include") {
+			let name = trimmed["#include".len()..].trim();
+			if name.len() < 2 || !name.starts_with('"') || !name.ends_with('"') {
+				return Err(RendererError::MalformedInclude {
+					path: path.into(),
+					line: i + 1,
+				});
+			}
+			let included = path
+				.parent()
+				.unwrap_or_else(|| Path::new(""))
+				.join(&name[1..name.len() - 1]);
+			let source = read(&included)?;
+			expand(files, stack, &included, source.lines().enumerate(), lines)?;
+		} else {
+			lines.push((line.into(), Some((index, i + 1))));
+		}
+	}
+
+	stack.pop();
+	Ok(())
+}
+
+fn finish_stage(
+	type_: ShaderType,
+	mut lines: Vec<Line>,
+	defines: &[(String, String)],
+) -> StageSource {
+	// #version has to come first, so the defines go right after it
+	let at = lines
+		.iter()
+		.position(|(line, _)| line.trim_start().starts_with("#version"))
+		.map_or(0, |i| i + 1);
+	for (i, (name, value)) in defines.iter().enumerate() {
+		lines.insert(at + i, (format!("#define {} {}", name, value), None));
+	}
+
+	let mut source = String::new();
+	let mut origins = vec![];
+	for (line, origin) in lines {
+		source.push_str(&line);
+		source.push('\n');
+		origins.push(origin);
+	}
+
+	StageSource {
+		type_,
+		source,
+		origins,
+	}
+}
+
+fn file_index(files: &mut Vec<PathBuf>, path: &Path) -> usize {
+	match files.iter().position(|f| f == path) {
+		Some(i) => i,
+		None => {
+			files.push(path.into());
+			files.len() - 1
+		}
+	}
+}
+
+fn read(path: &Path) -> Result<String, RendererError> {
+	fs::read_to_string(path).map_err(|error| RendererError::Io {
+		path: path.into(),
+		error,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn temp_dir(name: &str) -> PathBuf {
+		let dir = std::env::temp_dir().join(format!("wrath-{}-{}", name, std::process::id()));
+		fs::create_dir_all(&dir).unwrap();
+		dir
+	}
+
+	#[test]
+	fn includes_and_defines() {
+		let dir = temp_dir("preprocess");
+		fs::write(
+			dir.join("common.glsl"),
+			"float twice(float x) {\n\treturn x * 2.0;\n}\n",
+		)
+		.unwrap();
+		fs::write(
+			dir.join("shader.glsl"),
+			"#type vertex\n#version 330 core\n#include \"common.glsl\"\nvoid main() {}\n#type fragment\n#version 330 core\nvoid main() {}\n",
+		)
+		.unwrap();
+
+		let source =
+			preprocess(&dir.join("shader.glsl"), &[("LIGHTING".into(), "1".into())]).unwrap();
+
+		assert_eq!(source.files.len(), 2);
+		assert_eq!(source.stages[0].type_, ShaderType::Vertex);
+		assert_eq!(
+			source.stages[0].source,
+			"#version 330 core\n#define LIGHTING 1\nfloat twice(float x) {\n\treturn x * 2.0;\n}\nvoid main() {}\n"
+		);
+		assert_eq!(source.stages[1].type_, ShaderType::Fragment);
+
+		let log = source.map_log(
+			ShaderType::Vertex,
+			"0:4(2): error: oops\n0(6) : error: oops",
+		);
+		assert_eq!(
+			log,
+			format!(
+				"{0}:2(2): error: oops\n{1}:4 : error: oops\n",
+				dir.join("common.glsl").display(),
+				dir.join("shader.glsl").display(),
+			)
+		);
+	}
+
+	#[test]
+	fn include_cycle() {
+		let dir = temp_dir("cycle");
+		fs::write(dir.join("a.glsl"), "#include \"b.glsl\"\n").unwrap();
+		fs::write(dir.join("b.glsl"), "#include \"a.glsl\"\n").unwrap();
+		fs::write(
+			dir.join("shader.glsl"),
+			"#type vertex\n#include \"a.glsl\"\n",
+		)
+		.unwrap();
+
+		match preprocess(&dir.join("shader.glsl"), &[]) {
+			Err(RendererError::IncludeCycle(files)) => assert_eq!(files.len(), 4),
+			_ => panic!("expected an include cycle"),
+		}
+	}
+}
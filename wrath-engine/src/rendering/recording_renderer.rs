@@ -0,0 +1,906 @@
+use super::{
+	error::RendererError,
+	framebuffer::{DepthFormat, FramebufferHandle, FramebufferSpec},
+	mesh::{
+		self, BufferElement, BufferLayout, BufferUsage, DrawParams, Indices, InstanceBufferHandle,
+		MeshHandle, Vertices,
+	},
+	reflection::ShaderReflection,
+	render_state::RenderState,
+	shader::{ShaderHandle, ShaderType, ShaderUniform},
+	texture::{self, SamplerState, TextureFormat, TextureHandle},
+	uniform_buffer::{UniformBlockLayout, UniformBufferHandle},
+	Renderer,
+};
+
+use whm::Vector3;
+
+use std::{
+	cell::RefCell,
+	collections::{HashMap, HashSet},
+	path::{Path, PathBuf},
+	rc::Rc,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RenderCommand {
+	Clear,
+	SetClearColor(Vector3),
+	SetRenderState(RenderState),
+	SetViewport {
+		position: (i32, i32),
+		size: (u32, u32),
+	},
+	CreateShader {
+		handle: ShaderHandle,
+		path: PathBuf,
+		defines: Vec<(String, String)>,
+	},
+	CreateShaderFromSource {
+		handle: ShaderHandle,
+		stages: Vec<ShaderType>,
+	},
+	ReloadShader(ShaderHandle),
+	BindShader(ShaderHandle),
+	DeleteShader(ShaderHandle),
+	SetUniform {
+		handle: ShaderHandle,
+		name: String,
+		value: ShaderUniform,
+	},
+	CreateMesh {
+		handle: MeshHandle,
+		vertices_size: usize,
+		layout: Vec<BufferElement>,
+		index_count: usize,
+		usage: BufferUsage,
+	},
+	UpdateVertices {
+		handle: MeshHandle,
+		offset: usize,
+		vertices_size: usize,
+	},
+	UpdateIndices {
+		handle: MeshHandle,
+		index_count: usize,
+	},
+	BindMesh(MeshHandle),
+	DeleteMesh(MeshHandle),
+	CreateInstanceBuffer {
+		handle: InstanceBufferHandle,
+		instances_size: usize,
+		layout: Vec<BufferElement>,
+		divisor: u32,
+		usage: BufferUsage,
+	},
+	UpdateInstanceBuffer {
+		handle: InstanceBufferHandle,
+		offset: usize,
+		instances_size: usize,
+	},
+	DeleteInstanceBuffer(InstanceBufferHandle),
+	CreateUniformBuffer {
+		handle: UniformBufferHandle,
+		name: String,
+		size: usize,
+	},
+	UpdateUniformBuffer {
+		handle: UniformBufferHandle,
+		data: Vec<u8>,
+	},
+	DeleteUniformBuffer(UniformBufferHandle),
+	CreateTexture {
+		handle: TextureHandle,
+		size: (u32, u32),
+		format: TextureFormat,
+	},
+	UpdateTextureRegion {
+		handle: TextureHandle,
+		offset: (u32, u32),
+		size: (u32, u32),
+	},
+	SetTextureSampler {
+		handle: TextureHandle,
+		sampler: SamplerState,
+	},
+	BindTexture {
+		handle: TextureHandle,
+		unit: u32,
+	},
+	DeleteTexture(TextureHandle),
+	CreateFramebuffer {
+		handle: FramebufferHandle,
+		size: (u32, u32),
+		color: Vec<TextureFormat>,
+		depth: Option<DepthFormat>,
+		samples: u32,
+	},
+	ResizeFramebuffer {
+		handle: FramebufferHandle,
+		size: (u32, u32),
+	},
+	BindFramebuffer(FramebufferHandle),
+	BindDefaultFramebuffer,
+	ReadFramebuffer {
+		handle: FramebufferHandle,
+		index: usize,
+		offset: (u32, u32),
+		size: (u32, u32),
+	},
+	DeleteFramebuffer(FramebufferHandle),
+	Render {
+		mesh: MeshHandle,
+		shader: ShaderHandle,
+		params: DrawParams,
+	},
+	RenderInstanced {
+		mesh: MeshHandle,
+		shader: ShaderHandle,
+		instances: InstanceBufferHandle,
+		count: usize,
+	},
+	DispatchCompute {
+		shader: ShaderHandle,
+		groups: (u32, u32, u32),
+	},
+}
+
+pub struct RecordingRenderer {
+	log: RenderLog,
+	handle_counter: u32,
+	// nothing is compiled, so every shader reflects as empty
+	reflection: ShaderReflection,
+	render_state: RenderState,
+	// the textures set as uniforms of every shader, checked before drawing
+	shader_textures: HashMap<ShaderHandle, HashMap<String, TextureHandle>>,
+	error_callback: Box<dyn FnMut(&RendererError)>,
+}
+
+impl RecordingRenderer {
+	pub fn new() -> Self {
+		let log = RenderLog(Rc::new(RefCell::new(RenderLogInner {
+			commands: vec![],
+			errors: vec![],
+			shaders: HashSet::new(),
+			meshes: HashMap::new(),
+			instance_buffers: HashMap::new(),
+			uniform_buffers: HashMap::new(),
+			textures: HashMap::new(),
+			framebuffers: HashMap::new(),
+		})));
+		let errors = log.clone();
+		Self {
+			log,
+			handle_counter: 1,
+			reflection: ShaderReflection::default(),
+			render_state: RenderState::default(),
+			shader_textures: HashMap::new(),
+			error_callback: box move |error: &RendererError| {
+				errors.0.borrow_mut().errors.push(error.to_string())
+			},
+		}
+	}
+	pub fn log(&self) -> RenderLog {
+		self.log.clone()
+	}
+	fn next_handle(&mut self) -> u32 {
+		let id = self.handle_counter;
+		self.handle_counter += 1;
+		id
+	}
+	fn push(&mut self, command: RenderCommand) {
+		self.log.0.borrow_mut().commands.push(command);
+	}
+	fn check_shader(&self, handle: ShaderHandle) -> Result<(), RendererError> {
+		if self.log.0.borrow().shaders.contains(&handle) {
+			Ok(())
+		} else {
+			Err(RendererError::InvalidShaderHandle(handle))
+		}
+	}
+	fn texture(&self, handle: TextureHandle) -> Result<((u32, u32), TextureFormat), RendererError> {
+		self.log
+			.0
+			.borrow()
+			.textures
+			.get(&handle)
+			.copied()
+			.ok_or(RendererError::InvalidTextureHandle(handle))
+	}
+	fn instance_buffer(
+		&self,
+		handle: InstanceBufferHandle,
+	) -> Result<RecordedInstances, RendererError> {
+		self.log
+			.0
+			.borrow()
+			.instance_buffers
+			.get(&handle)
+			.copied()
+			.ok_or(RendererError::InvalidInstanceBufferHandle(handle))
+	}
+	fn framebuffer_textures(
+		&self,
+		handle: FramebufferHandle,
+	) -> Result<Vec<TextureHandle>, RendererError> {
+		self.log
+			.0
+			.borrow()
+			.framebuffers
+			.get(&handle)
+			.cloned()
+			.ok_or(RendererError::InvalidFramebufferHandle(handle))
+	}
+	// the shader and every texture it samples have to be alive to draw with it
+	fn check_shader_textures(&self, handle: ShaderHandle) -> Result<(), RendererError> {
+		self.check_shader(handle)?;
+		for texture in self
+			.shader_textures
+			.get(&handle)
+			.into_iter()
+			.flat_map(|t| t.values())
+		{
+			self.texture(*texture)?;
+		}
+		Ok(())
+	}
+	fn check_mesh(&self, handle: MeshHandle) -> Result<(), RendererError> {
+		self.mesh(handle).map(|_| ())
+	}
+	fn mesh(&self, handle: MeshHandle) -> Result<RecordedMesh, RendererError> {
+		self.log
+			.0
+			.borrow()
+			.meshes
+			.get(&handle)
+			.copied()
+			.ok_or(RendererError::InvalidMeshHandle(handle))
+	}
+}
+
+impl Renderer for RecordingRenderer {
+	fn clear(&mut self) {
+		self.push(RenderCommand::Clear);
+	}
+	fn set_clear_color(&mut self, color: Vector3) {
+		self.push(RenderCommand::SetClearColor(color));
+	}
+	fn set_render_state(&mut self, state: &RenderState) {
+		self.render_state = *state;
+		self.push(RenderCommand::SetRenderState(*state));
+	}
+	fn render_state(&self) -> &RenderState {
+		&self.render_state
+	}
+	fn set_viewport(&mut self, position: (i32, i32), size: (u32, u32)) {
+		self.push(RenderCommand::SetViewport { position, size });
+	}
+	fn create_shader_with_defines(
+		&mut self,
+		path: &Path,
+		defines: &[(&str, &str)],
+	) -> Result<ShaderHandle, RendererError> {
+		let handle = ShaderHandle::new(self.next_handle());
+		self.log.0.borrow_mut().shaders.insert(handle);
+		self.push(RenderCommand::CreateShader {
+			handle,
+			path: path.into(),
+			defines: defines
+				.iter()
+				.map(|(name, value)| (name.to_string(), value.to_string()))
+				.collect(),
+		});
+		Ok(handle)
+	}
+	fn create_shader_from_source(
+		&mut self,
+		stages: &[(ShaderType, &str)],
+	) -> Result<ShaderHandle, RendererError> {
+		let handle = ShaderHandle::new(self.next_handle());
+		self.log.0.borrow_mut().shaders.insert(handle);
+		self.push(RenderCommand::CreateShaderFromSource {
+			handle,
+			stages: stages.iter().map(|(type_, _)| *type_).collect(),
+		});
+		Ok(handle)
+	}
+	fn reload_shader(&mut self, handle: ShaderHandle) -> Result<(), RendererError> {
+		self.check_shader(handle)?;
+		self.push(RenderCommand::ReloadShader(handle));
+		Ok(())
+	}
+	fn set_shader_fallback(&mut self, _enabled: bool) {}
+	fn set_shader_hot_reload(&mut self, _enabled: bool) {}
+	fn poll_shader_changes(&mut self) {}
+	// replaces the default callback, which collects the errors in the log
+	fn set_error_callback(&mut self, callback: Box<dyn FnMut(&RendererError)>) {
+		self.error_callback = callback;
+	}
+	fn report_error(&mut self, error: &RendererError) {
+		(self.error_callback)(error);
+	}
+	fn bind_shader(&mut self, handle: ShaderHandle) -> Result<(), RendererError> {
+		self.check_shader(handle)?;
+		self.push(RenderCommand::BindShader(handle));
+		Ok(())
+	}
+	fn delete_shader(&mut self, handle: ShaderHandle) -> Result<(), RendererError> {
+		if !self.log.0.borrow_mut().shaders.remove(&handle) {
+			return Err(RendererError::InvalidShaderHandle(handle));
+		}
+		self.shader_textures.remove(&handle);
+		self.push(RenderCommand::DeleteShader(handle));
+		Ok(())
+	}
+	fn shader_info(&self, handle: ShaderHandle) -> Result<&ShaderReflection, RendererError> {
+		self.check_shader(handle)?;
+		Ok(&self.reflection)
+	}
+	fn set_uniform(&mut self, handle: ShaderHandle, name: &str, value: ShaderUniform) {
+		if let Err(error) = self.check_shader(handle) {
+			(self.error_callback)(&error);
+			return;
+		}
+		if let ShaderUniform::Texture(texture) = value {
+			self.shader_textures
+				.entry(handle)
+				.or_default()
+				.insert(name.into(), texture);
+		}
+		self.push(RenderCommand::SetUniform {
+			handle,
+			name: name.into(),
+			value,
+		});
+	}
+	fn create_mesh_from_layout(
+		&mut self,
+		vertices: &Vertices,
+		layout: &BufferLayout,
+		indices: &Indices,
+		usage: BufferUsage,
+	) -> Result<MeshHandle, RendererError> {
+		if layout.stride == 0 || vertices.size() % layout.stride != 0 {
+			return Err(RendererError::VertexLayoutMismatch {
+				size: vertices.size(),
+				stride: layout.stride,
+			});
+		}
+		let handle = MeshHandle::new(self.next_handle());
+		self.log.0.borrow_mut().meshes.insert(
+			handle,
+			RecordedMesh {
+				stride: layout.stride,
+				usage,
+				vertex_count: vertices.size() / layout.stride,
+				index_count: indices.len(),
+			},
+		);
+		self.push(RenderCommand::CreateMesh {
+			handle,
+			vertices_size: vertices.size(),
+			layout: layout.types.clone(),
+			index_count: indices.len(),
+			usage,
+		});
+		Ok(handle)
+	}
+	fn update_vertices(
+		&mut self,
+		handle: MeshHandle,
+		offset: usize,
+		vertices: &Vertices,
+	) -> Result<(), RendererError> {
+		let stride = self.mesh(handle)?.stride;
+		if vertices.size() % stride != 0 {
+			return Err(RendererError::VertexLayoutMismatch {
+				size: vertices.size(),
+				stride,
+			});
+		}
+		let mut log = self.log.0.borrow_mut();
+		let mesh = log.meshes.get_mut(&handle).unwrap();
+		mesh.vertex_count = written_count(
+			mesh.usage,
+			mesh.vertex_count,
+			offset,
+			vertices.size() / stride,
+		);
+		drop(log);
+		self.push(RenderCommand::UpdateVertices {
+			handle,
+			offset,
+			vertices_size: vertices.size(),
+		});
+		Ok(())
+	}
+	fn update_indices(
+		&mut self,
+		handle: MeshHandle,
+		indices: &Indices,
+	) -> Result<(), RendererError> {
+		match self.log.0.borrow_mut().meshes.get_mut(&handle) {
+			Some(mesh) => mesh.index_count = indices.len(),
+			None => return Err(RendererError::InvalidMeshHandle(handle)),
+		}
+		self.push(RenderCommand::UpdateIndices {
+			handle,
+			index_count: indices.len(),
+		});
+		Ok(())
+	}
+	fn bind_mesh(&mut self, handle: MeshHandle) -> Result<(), RendererError> {
+		self.check_mesh(handle)?;
+		self.push(RenderCommand::BindMesh(handle));
+		Ok(())
+	}
+	fn delete_mesh(&mut self, handle: MeshHandle) -> Result<(), RendererError> {
+		if self.log.0.borrow_mut().meshes.remove(&handle).is_none() {
+			return Err(RendererError::InvalidMeshHandle(handle));
+		}
+		self.push(RenderCommand::DeleteMesh(handle));
+		Ok(())
+	}
+	fn create_instance_buffer(
+		&mut self,
+		instances: &Vertices,
+		layout: &BufferLayout,
+		usage: BufferUsage,
+	) -> Result<InstanceBufferHandle, RendererError> {
+		if layout.stride == 0 || instances.size() % layout.stride != 0 {
+			return Err(RendererError::VertexLayoutMismatch {
+				size: instances.size(),
+				stride: layout.stride,
+			});
+		}
+		let handle = InstanceBufferHandle::new(self.next_handle());
+		self.log.0.borrow_mut().instance_buffers.insert(
+			handle,
+			RecordedInstances {
+				stride: layout.stride,
+				usage,
+				count: instances.size() / layout.stride,
+				divisor: layout.divisor,
+			},
+		);
+		self.push(RenderCommand::CreateInstanceBuffer {
+			handle,
+			instances_size: instances.size(),
+			layout: layout.types.clone(),
+			divisor: layout.divisor,
+			usage,
+		});
+		Ok(handle)
+	}
+	fn update_instance_buffer(
+		&mut self,
+		handle: InstanceBufferHandle,
+		offset: usize,
+		instances: &Vertices,
+	) -> Result<(), RendererError> {
+		let stride = self.instance_buffer(handle)?.stride;
+		if instances.size() % stride != 0 {
+			return Err(RendererError::VertexLayoutMismatch {
+				size: instances.size(),
+				stride,
+			});
+		}
+		let mut log = self.log.0.borrow_mut();
+		let buffer = log.instance_buffers.get_mut(&handle).unwrap();
+		buffer.count = written_count(
+			buffer.usage,
+			buffer.count,
+			offset,
+			instances.size() / stride,
+		);
+		drop(log);
+		self.push(RenderCommand::UpdateInstanceBuffer {
+			handle,
+			offset,
+			instances_size: instances.size(),
+		});
+		Ok(())
+	}
+	fn delete_instance_buffer(
+		&mut self,
+		handle: InstanceBufferHandle,
+	) -> Result<(), RendererError> {
+		if self
+			.log
+			.0
+			.borrow_mut()
+			.instance_buffers
+			.remove(&handle)
+			.is_none()
+		{
+			return Err(RendererError::InvalidInstanceBufferHandle(handle));
+		}
+		self.push(RenderCommand::DeleteInstanceBuffer(handle));
+		Ok(())
+	}
+	fn create_uniform_buffer(
+		&mut self,
+		name: &str,
+		layout: &UniformBlockLayout,
+	) -> Result<UniformBufferHandle, RendererError> {
+		let handle = UniformBufferHandle::new(self.next_handle());
+		self.log
+			.0
+			.borrow_mut()
+			.uniform_buffers
+			.insert(handle, (name.into(), layout.size));
+		self.push(RenderCommand::CreateUniformBuffer {
+			handle,
+			name: name.into(),
+			size: layout.size,
+		});
+		Ok(handle)
+	}
+	fn update_uniform_buffer(
+		&mut self,
+		handle: UniformBufferHandle,
+		data: &[u8],
+	) -> Result<(), RendererError> {
+		let (name, size) = self
+			.log
+			.0
+			.borrow()
+			.uniform_buffers
+			.get(&handle)
+			.cloned()
+			.ok_or(RendererError::InvalidUniformBufferHandle(handle))?;
+		if data.len() != size {
+			return Err(RendererError::UniformBlockSizeMismatch {
+				name,
+				size: data.len(),
+				expected: size,
+			});
+		}
+		self.push(RenderCommand::UpdateUniformBuffer {
+			handle,
+			data: data.into(),
+		});
+		Ok(())
+	}
+	fn delete_uniform_buffer(&mut self, handle: UniformBufferHandle) -> Result<(), RendererError> {
+		if self
+			.log
+			.0
+			.borrow_mut()
+			.uniform_buffers
+			.remove(&handle)
+			.is_none()
+		{
+			return Err(RendererError::InvalidUniformBufferHandle(handle));
+		}
+		self.push(RenderCommand::DeleteUniformBuffer(handle));
+		Ok(())
+	}
+	fn create_texture(
+		&mut self,
+		size: (u32, u32),
+		format: TextureFormat,
+		data: Option<&[u8]>,
+		_sampler: &SamplerState,
+	) -> Result<TextureHandle, RendererError> {
+		if let Some(data) = data {
+			texture::check_region(size, format, (0, 0), size, data)?;
+		}
+		let handle = TextureHandle::new(self.next_handle());
+		self.log
+			.0
+			.borrow_mut()
+			.textures
+			.insert(handle, (size, format));
+		self.push(RenderCommand::CreateTexture {
+			handle,
+			size,
+			format,
+		});
+		Ok(handle)
+	}
+	fn update_texture_region(
+		&mut self,
+		handle: TextureHandle,
+		offset: (u32, u32),
+		size: (u32, u32),
+		data: &[u8],
+	) -> Result<(), RendererError> {
+		let (texture_size, format) = self.texture(handle)?;
+		texture::check_region(texture_size, format, offset, size, data)?;
+		self.push(RenderCommand::UpdateTextureRegion {
+			handle,
+			offset,
+			size,
+		});
+		Ok(())
+	}
+	fn set_texture_sampler(
+		&mut self,
+		handle: TextureHandle,
+		sampler: &SamplerState,
+	) -> Result<(), RendererError> {
+		self.texture(handle)?;
+		self.push(RenderCommand::SetTextureSampler {
+			handle,
+			sampler: *sampler,
+		});
+		Ok(())
+	}
+	fn texture_size(&self, handle: TextureHandle) -> Result<(u32, u32), RendererError> {
+		self.texture(handle).map(|(size, _)| size)
+	}
+	fn bind_texture(&mut self, handle: TextureHandle, unit: u32) -> Result<(), RendererError> {
+		self.texture(handle)?;
+		self.push(RenderCommand::BindTexture { handle, unit });
+		Ok(())
+	}
+	fn delete_texture(&mut self, handle: TextureHandle) -> Result<(), RendererError> {
+		self.texture(handle)?;
+		let owner = self
+			.log
+			.0
+			.borrow()
+			.framebuffers
+			.iter()
+			.find(|(_, textures)| textures.contains(&handle))
+			.map(|(framebuffer, _)| *framebuffer);
+		if let Some(framebuffer) = owner {
+			return Err(RendererError::TextureOwnedByFramebuffer {
+				texture: handle,
+				framebuffer,
+			});
+		}
+		self.log.0.borrow_mut().textures.remove(&handle);
+		self.push(RenderCommand::DeleteTexture(handle));
+		Ok(())
+	}
+	fn create_framebuffer(
+		&mut self,
+		spec: &FramebufferSpec,
+	) -> Result<FramebufferHandle, RendererError> {
+		let textures = spec
+			.color
+			.iter()
+			.map(|format| self.create_texture(spec.size, *format, None, &spec.sampler))
+			.collect::<Result<Vec<_>, _>>()?;
+		let handle = FramebufferHandle::new(self.next_handle());
+		self.log
+			.0
+			.borrow_mut()
+			.framebuffers
+			.insert(handle, textures);
+		self.push(RenderCommand::CreateFramebuffer {
+			handle,
+			size: spec.size,
+			color: spec.color.clone(),
+			depth: spec.depth,
+			samples: spec.samples,
+		});
+		Ok(handle)
+	}
+	fn resize_framebuffer(
+		&mut self,
+		handle: FramebufferHandle,
+		size: (u32, u32),
+	) -> Result<(), RendererError> {
+		let textures = self.framebuffer_textures(handle)?;
+		for &texture in &textures {
+			self.texture(texture)?;
+		}
+		let mut log = self.log.0.borrow_mut();
+		for texture in textures {
+			if let Some(texture) = log.textures.get_mut(&texture) {
+				texture.0 = size;
+			}
+		}
+		drop(log);
+		self.push(RenderCommand::ResizeFramebuffer { handle, size });
+		Ok(())
+	}
+	fn bind_framebuffer(&mut self, handle: FramebufferHandle) -> Result<(), RendererError> {
+		self.framebuffer_textures(handle)?;
+		self.push(RenderCommand::BindFramebuffer(handle));
+		Ok(())
+	}
+	fn bind_default_framebuffer(&mut self) {
+		self.push(RenderCommand::BindDefaultFramebuffer);
+	}
+	fn framebuffer_texture(
+		&self,
+		handle: FramebufferHandle,
+		index: usize,
+	) -> Result<TextureHandle, RendererError> {
+		self.framebuffer_textures(handle)?
+			.get(index)
+			.copied()
+			.ok_or(RendererError::InvalidFramebufferAttachment { handle, index })
+	}
+	fn read_framebuffer(
+		&mut self,
+		handle: FramebufferHandle,
+		index: usize,
+		offset: (u32, u32),
+		size: (u32, u32),
+	) -> Result<Vec<u8>, RendererError> {
+		let (texture_size, format) = self.texture(self.framebuffer_texture(handle, index)?)?;
+		// nothing is drawn, so everything reads as zero
+		let pixels = vec![0; size.0 as usize * size.1 as usize * format.bytes_per_pixel()];
+		texture::check_region(texture_size, format, offset, size, &pixels)?;
+		self.push(RenderCommand::ReadFramebuffer {
+			handle,
+			index,
+			offset,
+			size,
+		});
+		Ok(pixels)
+	}
+	fn delete_framebuffer(&mut self, handle: FramebufferHandle) -> Result<(), RendererError> {
+		let textures = self.framebuffer_textures(handle)?;
+		let mut log = self.log.0.borrow_mut();
+		log.framebuffers.remove(&handle);
+		for texture in &textures {
+			log.textures.remove(texture);
+		}
+		drop(log);
+		for texture in textures {
+			self.push(RenderCommand::DeleteTexture(texture));
+		}
+		self.push(RenderCommand::DeleteFramebuffer(handle));
+		Ok(())
+	}
+	// like the gl renderer, draws that would fail are reported and skipped
+	fn render_with(&mut self, mesh: MeshHandle, shader: ShaderHandle, params: &DrawParams) {
+		let checked = self.mesh(mesh).and_then(|recorded| {
+			params.check(mesh, recorded.index_count, recorded.vertex_count)?;
+			self.check_shader_textures(shader)
+		});
+		if let Err(error) = checked {
+			(self.error_callback)(&error);
+			return;
+		}
+		self.push(RenderCommand::Render {
+			mesh,
+			shader,
+			params: *params,
+		});
+	}
+	fn render_instanced(
+		&mut self,
+		mesh: MeshHandle,
+		shader: ShaderHandle,
+		instances: InstanceBufferHandle,
+		count: usize,
+	) {
+		let checked = self.check_mesh(mesh).and_then(|_| {
+			let buffer = self.instance_buffer(instances)?;
+			mesh::check_instances(instances, buffer.count, buffer.divisor, count)?;
+			self.check_shader_textures(shader)
+		});
+		if let Err(error) = checked {
+			(self.error_callback)(&error);
+			return;
+		}
+		self.push(RenderCommand::RenderInstanced {
+			mesh,
+			shader,
+			instances,
+			count,
+		});
+	}
+	fn dispatch_compute(&mut self, shader: ShaderHandle, groups: (u32, u32, u32)) {
+		if let Err(error) = self.check_shader_textures(shader) {
+			(self.error_callback)(&error);
+			return;
+		}
+		self.push(RenderCommand::DispatchCompute { shader, groups });
+	}
+}
+
+struct RenderLogInner {
+	commands: Vec<RenderCommand>,
+	// what the default error callback was given
+	errors: Vec<String>,
+	shaders: HashSet<ShaderHandle>,
+	meshes: HashMap<MeshHandle, RecordedMesh>,
+	instance_buffers: HashMap<InstanceBufferHandle, RecordedInstances>,
+	// name and size of every live uniform buffer
+	uniform_buffers: HashMap<UniformBufferHandle, (String, usize)>,
+	textures: HashMap<TextureHandle, ((u32, u32), TextureFormat)>,
+	// color textures of every live framebuffer
+	framebuffers: HashMap<FramebufferHandle, Vec<TextureHandle>>,
+}
+
+// like the gl buffers, stream buffers written from the start are orphaned and
+// only hold what was just written
+fn written_count(usage: BufferUsage, count: usize, offset: usize, written: usize) -> usize {
+	if usage == BufferUsage::Stream && offset == 0 {
+		written
+	} else {
+		count.max(offset + written)
+	}
+}
+
+#[derive(Clone, Copy)]
+struct RecordedMesh {
+	stride: usize,
+	usage: BufferUsage,
+	vertex_count: usize,
+	index_count: usize,
+}
+
+#[derive(Clone, Copy)]
+struct RecordedInstances {
+	stride: usize,
+	usage: BufferUsage,
+	count: usize,
+	divisor: u32,
+}
+
+#[derive(Clone)]
+pub struct RenderLog(Rc<RefCell<RenderLogInner>>);
+
+impl RenderLog {
+	pub fn commands(&self) -> Vec<RenderCommand> {
+		self.0.borrow().commands.clone()
+	}
+	pub fn take_commands(&self) -> Vec<RenderCommand> {
+		std::mem::replace(&mut self.0.borrow_mut().commands, vec![])
+	}
+	pub fn take_errors(&self) -> Vec<String> {
+		std::mem::replace(&mut self.0.borrow_mut().errors, vec![])
+	}
+	pub fn live_shaders(&self) -> Vec<ShaderHandle> {
+		self.0.borrow().shaders.iter().copied().collect()
+	}
+	pub fn live_meshes(&self) -> Vec<MeshHandle> {
+		self.0.borrow().meshes.keys().copied().collect()
+	}
+	pub fn live_instance_buffers(&self) -> Vec<InstanceBufferHandle> {
+		self.0.borrow().instance_buffers.keys().copied().collect()
+	}
+	pub fn live_uniform_buffers(&self) -> Vec<UniformBufferHandle> {
+		self.0.borrow().uniform_buffers.keys().copied().collect()
+	}
+	pub fn live_textures(&self) -> Vec<TextureHandle> {
+		self.0.borrow().textures.keys().copied().collect()
+	}
+	pub fn live_framebuffers(&self) -> Vec<FramebufferHandle> {
+		self.0.borrow().framebuffers.keys().copied().collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn stale_handles_skip_draws() {
+		let renderer = RecordingRenderer::new();
+		let log = renderer.log();
+		let mut renderer: Box<dyn Renderer> = box renderer;
+		let shader = renderer.create_shader(Path::new("test.glsl")).unwrap();
+		let mesh = renderer
+			.create_mesh_from_layout(
+				&Vertices::new(vec![0.0; 3]),
+				&BufferLayout::new(&[BufferElement::Vector3]),
+				&Indices::U8(vec![0]),
+				BufferUsage::Static,
+			)
+			.unwrap();
+		let texture = renderer
+			.create_texture((1, 1), TextureFormat::Rgba8, None, &SamplerState::default())
+			.unwrap();
+		renderer.set_uniform(shader, "u_texture", texture.into());
+
+		renderer.delete_texture(texture).unwrap();
+		log.take_commands();
+		renderer.render(mesh, shader);
+		renderer.dispatch_compute(shader, (1, 1, 1));
+		renderer.set_uniform(ShaderHandle::none(), "u_value", 1.0.into());
+		renderer.render(MeshHandle::none(), shader);
+
+		assert!(log.take_commands().is_empty());
+		assert_eq!(log.take_errors().len(), 4);
+	}
+}
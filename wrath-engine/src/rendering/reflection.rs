@@ -0,0 +1,185 @@
+use super::shader::ShaderUniform;
+
+use std::fmt;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShaderReflection {
+	pub uniforms: Vec<UniformInfo>,
+	pub uniform_blocks: Vec<UniformBlockInfo>,
+	pub attributes: Vec<AttributeInfo>,
+}
+
+impl ShaderReflection {
+	pub fn uniform(&self, name: &str) -> Option<&UniformInfo> {
+		// arrays are listed as "name[0]" but may be set as "name" or "name[i]"
+		let first = match name.find('[') {
+			Some(i) => format!("{}[0]", &name[..i]),
+			None => format!("{}[0]", name),
+		};
+		self.uniforms
+			.iter()
+			.find(|u| u.name == name || u.name == first)
+	}
+	pub fn attribute(&self, name: &str) -> Option<&AttributeInfo> {
+		self.attributes.iter().find(|a| a.name == name)
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UniformInfo {
+	pub name: String,
+	pub type_: UniformType,
+	pub size: usize,
+	// -1 for members of uniform blocks
+	pub location: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UniformBlockInfo {
+	pub name: String,
+	pub index: u32,
+	pub size: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeInfo {
+	pub name: String,
+	pub type_: UniformType,
+	pub size: usize,
+	pub location: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UniformType {
+	Float,
+	Vector2,
+	Vector3,
+	Vector4,
+	Int,
+	IVector2,
+	IVector3,
+	IVector4,
+	UInt,
+	UVector2,
+	UVector3,
+	UVector4,
+	Bool,
+	Matrix2,
+	Matrix3,
+	Matrix4,
+	Sampler,
+	// any type the shader uniforms can't express, with the backend's type id
+	Other(u32),
+}
+
+impl UniformType {
+	pub fn accepts(self, value: &ShaderUniform) -> bool {
+		use ShaderUniform::*;
+		let type_ = match value {
+			Float(_) | FloatArray(_) => UniformType::Float,
+			Vector2(_) | Vector2Array(_) => UniformType::Vector2,
+			Vector3(_) | Vector3Array(_) => UniformType::Vector3,
+			Vector4(_) | Vector4Array(_) => UniformType::Vector4,
+			// bools and samplers are set through the integer functions too
+			I32(_) | I32Array(_) => {
+				return self == UniformType::Int
+					|| self == UniformType::Bool
+					|| self == UniformType::Sampler
+			}
+			IVector2(_) | IVector2Array(_) => UniformType::IVector2,
+			IVector3(_) | IVector3Array(_) => UniformType::IVector3,
+			IVector4(_) | IVector4Array(_) => UniformType::IVector4,
+			U32(_) | U32Array(_) => UniformType::UInt,
+			UVector2(_) | UVector2Array(_) => UniformType::UVector2,
+			UVector3(_) | UVector3Array(_) => UniformType::UVector3,
+			UVector4(_) | UVector4Array(_) => UniformType::UVector4,
+			Bool(_) | BoolArray(_) => UniformType::Bool,
+			Matrix2(..) | Matrix2Array(..) => UniformType::Matrix2,
+			Matrix3(..) | Matrix3Array(..) => UniformType::Matrix3,
+			Matrix4(..) | Matrix4Array(..) => UniformType::Matrix4,
+			Sampler(_) | SamplerArray(_) | Texture(_) => UniformType::Sampler,
+		};
+		self == type_
+	}
+}
+
+impl fmt::Display for UniformType {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			UniformType::Float => write!(f, "float"),
+			UniformType::Vector2 => write!(f, "vec2"),
+			UniformType::Vector3 => write!(f, "vec3"),
+			UniformType::Vector4 => write!(f, "vec4"),
+			UniformType::Int => write!(f, "int"),
+			UniformType::IVector2 => write!(f, "ivec2"),
+			UniformType::IVector3 => write!(f, "ivec3"),
+			UniformType::IVector4 => write!(f, "ivec4"),
+			UniformType::UInt => write!(f, "uint"),
+			UniformType::UVector2 => write!(f, "uvec2"),
+			UniformType::UVector3 => write!(f, "uvec3"),
+			UniformType::UVector4 => write!(f, "uvec4"),
+			UniformType::Bool => write!(f, "bool"),
+			UniformType::Matrix2 => write!(f, "mat2"),
+			UniformType::Matrix3 => write!(f, "mat3"),
+			UniformType::Matrix4 => write!(f, "mat4"),
+			UniformType::Sampler => write!(f, "sampler"),
+			UniformType::Other(id) => write!(f, "type {:#x}", id),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::TextureHandle;
+
+	fn uniform(name: &str, type_: UniformType, size: usize) -> UniformInfo {
+		UniformInfo {
+			name: name.into(),
+			type_,
+			size,
+			location: 0,
+		}
+	}
+
+	#[test]
+	fn uniform_types() {
+		assert!(UniformType::Float.accepts(&ShaderUniform::Float(1.0)));
+		assert!(UniformType::Float.accepts(&ShaderUniform::FloatArray(vec![1.0])));
+		assert!(!UniformType::Float.accepts(&ShaderUniform::I32(1)));
+		assert!(UniformType::UVector3.accepts(&ShaderUniform::UVector3([1, 2, 3])));
+		assert!(!UniformType::IVector3.accepts(&ShaderUniform::UVector3([1, 2, 3])));
+
+		// ints also set bools and samplers
+		assert!(UniformType::Int.accepts(&ShaderUniform::I32(1)));
+		assert!(UniformType::Bool.accepts(&ShaderUniform::I32(1)));
+		assert!(UniformType::Sampler.accepts(&ShaderUniform::I32Array(vec![0, 1])));
+		assert!(!UniformType::UInt.accepts(&ShaderUniform::I32(1)));
+		assert!(UniformType::Bool.accepts(&ShaderUniform::Bool(true)));
+		assert!(!UniformType::Int.accepts(&ShaderUniform::Bool(true)));
+
+		assert!(UniformType::Sampler.accepts(&ShaderUniform::Texture(TextureHandle::new(1))));
+		assert!(UniformType::Sampler.accepts(&ShaderUniform::Sampler(0)));
+		assert!(!UniformType::Int.accepts(&ShaderUniform::Sampler(0)));
+		assert!(!UniformType::Other(0).accepts(&ShaderUniform::Float(1.0)));
+	}
+
+	#[test]
+	fn uniform_lookup() {
+		let reflection = ShaderReflection {
+			uniforms: vec![
+				uniform("u_time", UniformType::Float, 1),
+				uniform("u_lights[0]", UniformType::Vector3, 4),
+			],
+			..Default::default()
+		};
+
+		assert_eq!(reflection.uniform("u_time").unwrap().name, "u_time");
+		assert!(reflection.uniform("u_time[1]").is_none());
+		assert!(reflection.uniform("u_missing").is_none());
+		// arrays are found by their name, their first element or any other one
+		for name in &["u_lights", "u_lights[0]", "u_lights[3]"] {
+			assert_eq!(reflection.uniform(name).unwrap().name, "u_lights[0]");
+		}
+	}
+}
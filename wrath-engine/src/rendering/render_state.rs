@@ -0,0 +1,115 @@
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderState {
+	pub depth_test: bool,
+	pub depth_write: bool,
+	pub depth_func: CompareFunc,
+	pub cull: CullMode,
+	pub blend: BlendMode,
+	// position and size in pixels from the bottom left, None draws everywhere
+	pub scissor: Option<((i32, i32), (u32, u32))>,
+	// red, green, blue and alpha
+	pub color_mask: [bool; 4],
+	// None turns the stencil test off
+	pub stencil: Option<StencilState>,
+}
+
+impl RenderState {
+	// depth tested and back face culled, for 3d geometry
+	pub fn opaque() -> Self {
+		Self {
+			depth_test: true,
+			cull: CullMode::Back,
+			blend: BlendMode::None,
+			..Default::default()
+		}
+	}
+}
+
+// alpha blended without depth testing, the state renderers start out in
+impl Default for RenderState {
+	fn default() -> Self {
+		Self {
+			depth_test: false,
+			depth_write: true,
+			depth_func: CompareFunc::Less,
+			cull: CullMode::None,
+			blend: BlendMode::Alpha,
+			scissor: None,
+			color_mask: [true; 4],
+			stencil: None,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareFunc {
+	Never,
+	Less,
+	Equal,
+	LessEqual,
+	Greater,
+	NotEqual,
+	GreaterEqual,
+	Always,
+}
+
+// faces are front facing when wound counter clockwise
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CullMode {
+	None,
+	Back,
+	Front,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendMode {
+	None,
+	// src * a + dst * (1 - a)
+	Alpha,
+	// src + dst * (1 - a), for colors already multiplied by their alpha
+	PremultipliedAlpha,
+	// src * a + dst
+	Additive,
+	// src * dst
+	Multiply,
+	// componentwise min and max of src and dst
+	Min,
+	Max,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StencilState {
+	pub func: CompareFunc,
+	pub reference: i32,
+	pub read_mask: u32,
+	pub write_mask: u32,
+	pub stencil_fail: StencilOp,
+	pub depth_fail: StencilOp,
+	pub pass: StencilOp,
+}
+
+impl Default for StencilState {
+	fn default() -> Self {
+		Self {
+			func: CompareFunc::Always,
+			reference: 0,
+			read_mask: !0,
+			write_mask: !0,
+			stencil_fail: StencilOp::Keep,
+			depth_fail: StencilOp::Keep,
+			pass: StencilOp::Keep,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StencilOp {
+	Keep,
+	Zero,
+	Replace,
+	Increment,
+	IncrementWrap,
+	Decrement,
+	DecrementWrap,
+	Invert,
+}
@@ -1,6 +1,14 @@
 use super::{
-	mesh::{BufferLayout, Indices, MeshHandle, Vertices},
-	shader::{ShaderHandle, ShaderUniform},
+	error::RendererError,
+	framebuffer::{FramebufferHandle, FramebufferSpec},
+	mesh::{
+		BufferLayout, BufferUsage, DrawParams, Indices, InstanceBufferHandle, MeshHandle, Vertices,
+	},
+	reflection::ShaderReflection,
+	render_state::RenderState,
+	shader::{ShaderHandle, ShaderType, ShaderUniform},
+	texture::{SamplerState, TextureFormat, TextureHandle},
+	uniform_buffer::{UniformBlockLayout, UniformBufferHandle},
 };
 
 use whm::Vector3;
@@ -10,17 +18,160 @@ use std::path::Path;
 pub trait Renderer {
 	fn clear(&mut self);
 	fn set_clear_color(&mut self, color: Vector3);
-	fn create_shader(&mut self, path: &Path) -> ShaderHandle;
-	fn bind_shader(&mut self, handle: ShaderHandle);
-	fn delete_shader(&mut self, handle: ShaderHandle);
+	// only the parts that changed since the last call reach the backend
+	fn set_render_state(&mut self, state: &RenderState);
+	fn render_state(&self) -> &RenderState;
+	// position and size in pixels from the bottom left
+	fn set_viewport(&mut self, position: (i32, i32), size: (u32, u32));
+	fn create_shader(&mut self, path: &Path) -> Result<ShaderHandle, RendererError> {
+		self.create_shader_with_defines(path, &[])
+	}
+	fn create_shader_with_defines(
+		&mut self,
+		path: &Path,
+		defines: &[(&str, &str)],
+	) -> Result<ShaderHandle, RendererError>;
+	// for built-in shaders, the sources aren't preprocessed and never reload
+	fn create_shader_from_source(
+		&mut self,
+		stages: &[(ShaderType, &str)],
+	) -> Result<ShaderHandle, RendererError>;
+	fn reload_shader(&mut self, handle: ShaderHandle) -> Result<(), RendererError>;
+	fn set_shader_fallback(&mut self, enabled: bool);
+	fn set_shader_hot_reload(&mut self, enabled: bool);
+	fn poll_shader_changes(&mut self);
+	fn set_error_callback(&mut self, callback: Box<dyn FnMut(&RendererError)>);
+	// for errors of the engine's own per-frame work, which has no caller to return them to
+	fn report_error(&mut self, error: &RendererError);
+	fn bind_shader(&mut self, handle: ShaderHandle) -> Result<(), RendererError>;
+	fn delete_shader(&mut self, handle: ShaderHandle) -> Result<(), RendererError>;
+	fn shader_info(&self, handle: ShaderHandle) -> Result<&ShaderReflection, RendererError>;
 	fn set_uniform(&mut self, handle: ShaderHandle, name: &str, value: ShaderUniform);
-	fn create_mesh(
+	// for vertices without a Vertex type, create_mesh takes a slice of them
+	fn create_mesh_from_layout(
 		&mut self,
 		vertices: &Vertices,
 		layout: &BufferLayout,
 		indices: &Indices,
-	) -> MeshHandle;
-	fn bind_mesh(&mut self, handle: MeshHandle);
-	fn delete_mesh(&mut self, handle: MeshHandle);
-	fn render(&mut self, mh: MeshHandle, sh: ShaderHandle);
+		usage: BufferUsage,
+	) -> Result<MeshHandle, RendererError>;
+	// offset is in vertices, the buffer grows if the data doesn't fit
+	fn update_vertices(
+		&mut self,
+		handle: MeshHandle,
+		offset: usize,
+		vertices: &Vertices,
+	) -> Result<(), RendererError>;
+	// replaces all indices, so the mesh draws indices.len() of them from now on
+	fn update_indices(
+		&mut self,
+		handle: MeshHandle,
+		indices: &Indices,
+	) -> Result<(), RendererError>;
+	fn bind_mesh(&mut self, handle: MeshHandle) -> Result<(), RendererError>;
+	fn delete_mesh(&mut self, handle: MeshHandle) -> Result<(), RendererError>;
+	// layout is usually BufferLayout::per_instance, its attributes follow the
+	// ones of the mesh it's drawn with
+	fn create_instance_buffer(
+		&mut self,
+		instances: &Vertices,
+		layout: &BufferLayout,
+		usage: BufferUsage,
+	) -> Result<InstanceBufferHandle, RendererError>;
+	// offset is in instance buffer elements, the buffer grows like mesh buffers
+	fn update_instance_buffer(
+		&mut self,
+		handle: InstanceBufferHandle,
+		offset: usize,
+		instances: &Vertices,
+	) -> Result<(), RendererError>;
+	fn delete_instance_buffer(&mut self, handle: InstanceBufferHandle)
+		-> Result<(), RendererError>;
+	fn create_uniform_buffer(
+		&mut self,
+		name: &str,
+		layout: &UniformBlockLayout,
+	) -> Result<UniformBufferHandle, RendererError>;
+	fn update_uniform_buffer(
+		&mut self,
+		handle: UniformBufferHandle,
+		data: &[u8],
+	) -> Result<(), RendererError>;
+	fn delete_uniform_buffer(&mut self, handle: UniformBufferHandle) -> Result<(), RendererError>;
+	fn create_texture(
+		&mut self,
+		size: (u32, u32),
+		format: TextureFormat,
+		data: Option<&[u8]>,
+		sampler: &SamplerState,
+	) -> Result<TextureHandle, RendererError>;
+	fn create_texture_from_file(
+		&mut self,
+		path: &Path,
+		sampler: &SamplerState,
+	) -> Result<TextureHandle, RendererError> {
+		let image = image::open(path).map_err(|error| RendererError::Image {
+			path: path.into(),
+			error,
+		})?;
+		// the first row of a texture is the bottom one
+		let image = image::imageops::flip_vertical(&image.to_rgba());
+		let size = image.dimensions();
+		self.create_texture(size, TextureFormat::Rgba8, Some(&image.into_raw()), sampler)
+	}
+	fn update_texture_region(
+		&mut self,
+		handle: TextureHandle,
+		offset: (u32, u32),
+		size: (u32, u32),
+		data: &[u8],
+	) -> Result<(), RendererError>;
+	fn set_texture_sampler(
+		&mut self,
+		handle: TextureHandle,
+		sampler: &SamplerState,
+	) -> Result<(), RendererError>;
+	fn texture_size(&self, handle: TextureHandle) -> Result<(u32, u32), RendererError>;
+	fn bind_texture(&mut self, handle: TextureHandle, unit: u32) -> Result<(), RendererError>;
+	fn delete_texture(&mut self, handle: TextureHandle) -> Result<(), RendererError>;
+	fn create_framebuffer(
+		&mut self,
+		spec: &FramebufferSpec,
+	) -> Result<FramebufferHandle, RendererError>;
+	// keeps the texture handles of the color attachments
+	fn resize_framebuffer(
+		&mut self,
+		handle: FramebufferHandle,
+		size: (u32, u32),
+	) -> Result<(), RendererError>;
+	// also sets the viewport to the size of the framebuffer
+	fn bind_framebuffer(&mut self, handle: FramebufferHandle) -> Result<(), RendererError>;
+	fn bind_default_framebuffer(&mut self);
+	// the texture is owned by the framebuffer and deleted along with it
+	fn framebuffer_texture(
+		&self,
+		handle: FramebufferHandle,
+		index: usize,
+	) -> Result<TextureHandle, RendererError>;
+	// pixels of a color attachment, tightly packed rows from the bottom up
+	fn read_framebuffer(
+		&mut self,
+		handle: FramebufferHandle,
+		index: usize,
+		offset: (u32, u32),
+		size: (u32, u32),
+	) -> Result<Vec<u8>, RendererError>;
+	fn delete_framebuffer(&mut self, handle: FramebufferHandle) -> Result<(), RendererError>;
+	fn render(&mut self, mh: MeshHandle, sh: ShaderHandle) {
+		self.render_with(mh, sh, &DrawParams::default())
+	}
+	fn render_with(&mut self, mh: MeshHandle, sh: ShaderHandle, params: &DrawParams);
+	fn render_instanced(
+		&mut self,
+		mh: MeshHandle,
+		sh: ShaderHandle,
+		instances: InstanceBufferHandle,
+		count: usize,
+	);
+	fn dispatch_compute(&mut self, sh: ShaderHandle, groups: (u32, u32, u32));
 }
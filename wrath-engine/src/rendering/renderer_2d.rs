@@ -0,0 +1,477 @@
+use super::{
+	error::RendererError,
+	font::{Font, TextOptions},
+	mesh::{BufferUsage, DrawParams, DrawRange, Indices, MeshHandle, Primitive},
+	render_state::RenderState,
+	shader::{ShaderHandle, ShaderType},
+	texture::{SamplerState, TextureFormat, TextureHandle},
+	Renderer,
+};
+
+use whm::Float;
+
+use std::f32::consts::PI;
+
+const VERTEX_SHADER: &str = "#version 330 core
+layout(location = 0) in vec2 in_pos;
+layout(location = 1) in vec2 in_uv;
+layout(location = 2) in vec4 in_color;
+out vec2 v_uv;
+out vec4 v_color;
+void main() {
+	gl_Position = vec4(in_pos, 0.0, 1.0);
+	v_uv = in_uv;
+	v_color = in_color;
+}
+";
+
+const FRAGMENT_SHADER: &str = "#version 330 core
+layout(location = 0) out vec4 out_color;
+in vec2 v_uv;
+in vec4 v_color;
+uniform sampler2D u_texture;
+void main() {
+	out_color = texture(u_texture, v_uv) * v_color;
+}
+";
+
+const CIRCLE_SEGMENTS: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+	pub x: Float,
+	pub y: Float,
+	pub width: Float,
+	pub height: Float,
+}
+
+impl Rect {
+	pub fn new(x: Float, y: Float, width: Float, height: Float) -> Self {
+		Self {
+			x,
+			y,
+			width,
+			height,
+		}
+	}
+	// the whole texture
+	pub fn unit() -> Self {
+		Self::new(0.0, 0.0, 1.0, 1.0)
+	}
+	pub fn contains(&self, point: (Float, Float)) -> bool {
+		point.0 >= self.x
+			&& point.1 >= self.y
+			&& point.0 < self.x + self.width
+			&& point.1 < self.y + self.height
+	}
+}
+
+#[derive(Clone, Copy, crate::Vertex)]
+#[repr(C)]
+struct Vertex2D {
+	// already in clip space
+	position: [Float; 2],
+	uv: [Float; 2],
+	color: [Float; 4],
+}
+
+// indices drawn with the same texture, none is the white texture
+struct Batch {
+	texture: TextureHandle,
+	first: usize,
+	count: usize,
+}
+
+// collects quads, sprites, lines and circles of a frame and draws them in as
+// few draw calls as possible. positions are in pixels from the top left of the
+// window unless a view is set
+pub struct Renderer2D {
+	shader: ShaderHandle,
+	mesh: MeshHandle,
+	white: TextureHandle,
+	vertices: Vec<Vertex2D>,
+	indices: Vec<u32>,
+	batches: Vec<Batch>,
+	// font atlas regions to write before drawing
+	uploads: Vec<(TextureHandle, (u32, u32), (u32, u32), Vec<u8>)>,
+	// returned by the next flush
+	error: Option<RendererError>,
+	viewport_size: (u32, u32),
+	view: Option<Rect>,
+}
+
+impl Renderer2D {
+	pub fn new() -> Self {
+		Self {
+			shader: ShaderHandle::none(),
+			mesh: MeshHandle::none(),
+			white: TextureHandle::none(),
+			vertices: vec![],
+			indices: vec![],
+			batches: vec![],
+			uploads: vec![],
+			error: None,
+			viewport_size: (1, 1),
+			view: None,
+		}
+	}
+	// the engine calls this when the window is resized
+	pub fn set_viewport_size(&mut self, size: (u32, u32)) {
+		self.viewport_size = (size.0.max(1), size.1.max(1));
+	}
+	// the part of the world that covers the window, x and y are its top left
+	// corner. None draws in pixels again
+	pub fn set_view(&mut self, view: Option<Rect>) {
+		self.view = view;
+	}
+	pub fn view(&self) -> Rect {
+		self.view.unwrap_or_else(|| {
+			Rect::new(
+				0.0,
+				0.0,
+				self.viewport_size.0 as Float,
+				self.viewport_size.1 as Float,
+			)
+		})
+	}
+	pub fn draw_quad(&mut self, position: (Float, Float), size: (Float, Float), color: [Float; 4]) {
+		let rect = Rect::new(position.0, position.1, size.0, size.1);
+		self.draw_sprite_tinted(TextureHandle::none(), rect, Rect::unit(), color);
+	}
+	// uv is in texture coordinates where 0, 0 is the bottom left of the texture
+	pub fn draw_sprite(&mut self, texture: TextureHandle, rect: Rect, uv: Rect) {
+		self.draw_sprite_tinted(texture, rect, uv, [1.0; 4]);
+	}
+	pub fn draw_sprite_tinted(
+		&mut self,
+		texture: TextureHandle,
+		rect: Rect,
+		uv: Rect,
+		color: [Float; 4],
+	) {
+		let (left, right) = (rect.x, rect.x + rect.width);
+		let (top, bottom) = (rect.y, rect.y + rect.height);
+		let (u0, u1) = (uv.x, uv.x + uv.width);
+		let (v0, v1) = (uv.y, uv.y + uv.height);
+		self.push_quad(
+			texture,
+			[
+				((left, top), [u0, v1]),
+				((right, top), [u1, v1]),
+				((right, bottom), [u1, v0]),
+				((left, bottom), [u0, v0]),
+			],
+			color,
+		);
+	}
+	pub fn draw_line(
+		&mut self,
+		from: (Float, Float),
+		to: (Float, Float),
+		thickness: Float,
+		color: [Float; 4],
+	) {
+		let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+		let length = (dx * dx + dy * dy).sqrt();
+		if length == 0.0 {
+			return;
+		}
+		let (nx, ny) = (
+			-dy / length * thickness / 2.0,
+			dx / length * thickness / 2.0,
+		);
+		self.push_quad(
+			TextureHandle::none(),
+			[
+				((from.0 + nx, from.1 + ny), [0.0, 0.0]),
+				((to.0 + nx, to.1 + ny), [0.0, 0.0]),
+				((to.0 - nx, to.1 - ny), [0.0, 0.0]),
+				((from.0 - nx, from.1 - ny), [0.0, 0.0]),
+			],
+			color,
+		);
+	}
+	pub fn draw_circle(&mut self, center: (Float, Float), radius: Float, color: [Float; 4]) {
+		let first = self.begin(TextureHandle::none());
+		self.push_vertex(center, [0.0, 0.0], color);
+		for i in 0..CIRCLE_SEGMENTS {
+			let angle = i as Float / CIRCLE_SEGMENTS as Float * 2.0 * PI;
+			let point = (
+				center.0 + angle.cos() * radius,
+				center.1 + angle.sin() * radius,
+			);
+			self.push_vertex(point, [0.0, 0.0], color);
+			let next = (i + 1) % CIRCLE_SEGMENTS;
+			self.push_indices(first, &[0, i as u32 + 1, next as u32 + 1]);
+		}
+	}
+	// position is the top left of the text, sizes are meant to be in pixels
+	pub fn draw_text(
+		&mut self,
+		font: &mut Font,
+		text: &str,
+		position: (Float, Float),
+		options: &TextOptions,
+		color: [Float; 4],
+	) {
+		let (glyphs, _) = font.layout(text, options);
+		for (c, pen) in glyphs {
+			let glyph = match font.glyph(c, options.size) {
+				Ok(Some(glyph)) => glyph,
+				Ok(None) => continue,
+				Err(error) => {
+					self.error = Some(error);
+					continue;
+				}
+			};
+			let rect = Rect::new(
+				position.0 + pen.0 + glyph.offset.0,
+				position.1 + pen.1 + glyph.offset.1,
+				glyph.size.0,
+				glyph.size.1,
+			);
+			self.draw_sprite_tinted(font.texture(), rect, glyph.uv, color);
+		}
+
+		let texture = font.texture();
+		self.uploads.extend(
+			font.take_uploads()
+				.into_iter()
+				.map(|(offset, size, pixels)| (texture, offset, size, pixels)),
+		);
+	}
+	// draws everything batched since the last flush, the engine calls this after
+	// every overlay. errors of draw_text are returned here after drawing the rest
+	pub fn flush(&mut self, renderer: &mut dyn Renderer) -> Result<(), RendererError> {
+		let drawn = self.draw(renderer);
+		// a batch that failed to draw is dropped too, it would fail again
+		self.vertices.clear();
+		self.indices.clear();
+		self.batches.clear();
+		self.uploads.clear();
+		drawn?;
+		self.take_error()
+	}
+	pub fn delete(&mut self, renderer: &mut dyn Renderer) -> Result<(), RendererError> {
+		if self.shader != ShaderHandle::none() {
+			renderer.delete_shader(self.shader)?;
+			renderer.delete_texture(self.white)?;
+		}
+		if self.mesh != MeshHandle::none() {
+			renderer.delete_mesh(self.mesh)?;
+		}
+		*self = Self {
+			viewport_size: self.viewport_size,
+			..Self::new()
+		};
+		Ok(())
+	}
+	fn draw(&mut self, renderer: &mut dyn Renderer) -> Result<(), RendererError> {
+		for (texture, offset, size, pixels) in &self.uploads {
+			renderer.update_texture_region(*texture, *offset, *size, pixels)?;
+		}
+		if self.indices.is_empty() {
+			return Ok(());
+		}
+		self.create_resources(renderer)?;
+
+		let indices = Indices::U32(std::mem::replace(&mut self.indices, vec![]));
+		if self.mesh == MeshHandle::none() {
+			self.mesh =
+				renderer.create_mesh_with_usage(&self.vertices, &indices, BufferUsage::Stream)?;
+		} else {
+			renderer.update_vertices_from_slice(self.mesh, 0, &self.vertices)?;
+			renderer.update_indices(self.mesh, &indices)?;
+		}
+
+		let previous = *renderer.render_state();
+		renderer.set_render_state(&RenderState::default());
+		for batch in &self.batches {
+			let texture = if batch.texture == TextureHandle::none() {
+				self.white
+			} else {
+				batch.texture
+			};
+			renderer.set_uniform(self.shader, "u_texture", texture.into());
+			renderer.render_with(
+				self.mesh,
+				self.shader,
+				&DrawParams::new(
+					Primitive::Triangles,
+					DrawRange::Indices {
+						first: batch.first,
+						count: batch.count,
+						base_vertex: 0,
+					},
+				),
+			);
+		}
+		renderer.set_render_state(&previous);
+
+		Ok(())
+	}
+	fn take_error(&mut self) -> Result<(), RendererError> {
+		match self.error.take() {
+			Some(error) => Err(error),
+			None => Ok(()),
+		}
+	}
+	fn create_resources(&mut self, renderer: &mut dyn Renderer) -> Result<(), RendererError> {
+		if self.shader != ShaderHandle::none() {
+			return Ok(());
+		}
+		self.shader = renderer.create_shader_from_source(&[
+			(ShaderType::Vertex, VERTEX_SHADER),
+			(ShaderType::Fragment, FRAGMENT_SHADER),
+		])?;
+		self.white = renderer.create_texture(
+			(1, 1),
+			TextureFormat::Rgba8,
+			Some(&[255; 4]),
+			&SamplerState::nearest(),
+		)?;
+		Ok(())
+	}
+	fn push_quad(
+		&mut self,
+		texture: TextureHandle,
+		corners: [((Float, Float), [Float; 2]); 4],
+		color: [Float; 4],
+	) {
+		let first = self.begin(texture);
+		for (position, uv) in corners.iter() {
+			self.push_vertex(*position, *uv, color);
+		}
+		self.push_indices(first, &[0, 1, 2, 2, 3, 0]);
+	}
+	// starts a new batch if the texture changed, returns the index of the
+	// first vertex about to be pushed
+	fn begin(&mut self, texture: TextureHandle) -> u32 {
+		let index_count = self.indices.len();
+		match self.batches.last() {
+			Some(batch) if batch.texture == texture => {}
+			_ => self.batches.push(Batch {
+				texture,
+				first: index_count,
+				count: 0,
+			}),
+		}
+		self.vertices.len() as u32
+	}
+	fn push_vertex(&mut self, position: (Float, Float), uv: [Float; 2], color: [Float; 4]) {
+		let view = self.view();
+		let x = (position.0 - view.x) / view.width * 2.0 - 1.0;
+		let y = 1.0 - (position.1 - view.y) / view.height * 2.0;
+		self.vertices.push(Vertex2D {
+			position: [x, y],
+			uv,
+			color,
+		});
+	}
+	fn push_indices(&mut self, first: u32, indices: &[u32]) {
+		self.indices.extend(indices.iter().map(|i| first + i));
+		self.batches.last_mut().unwrap().count += indices.len();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{Font, RecordingRenderer, RenderCommand, TextOptions};
+
+	#[test]
+	fn renderer_2d_batches() {
+		let renderer = RecordingRenderer::new();
+		let log = renderer.log();
+		let mut renderer: Box<dyn Renderer> = box renderer;
+		let texture = renderer
+			.create_texture((2, 2), TextureFormat::Rgba8, None, &SamplerState::default())
+			.unwrap();
+
+		let mut renderer_2d = Renderer2D::new();
+		renderer_2d.set_viewport_size((80, 60));
+		renderer_2d.flush(renderer.as_mut()).unwrap();
+		assert!(log.live_shaders().is_empty());
+
+		renderer_2d.draw_quad((0.0, 0.0), (10.0, 10.0), [1.0; 4]);
+		renderer_2d.draw_line((0.0, 0.0), (80.0, 60.0), 2.0, [1.0; 4]);
+		renderer_2d.draw_sprite(texture, Rect::new(10.0, 10.0, 20.0, 20.0), Rect::unit());
+		renderer_2d.draw_circle((40.0, 30.0), 5.0, [1.0; 4]);
+		log.take_commands();
+		renderer_2d.flush(renderer.as_mut()).unwrap();
+
+		let ranges = log
+			.take_commands()
+			.into_iter()
+			.filter_map(|c| match c {
+				RenderCommand::Render { params, .. } => Some(params.range),
+				_ => None,
+			})
+			.collect::<Vec<_>>();
+		assert_eq!(
+			ranges,
+			vec![
+				DrawRange::Indices {
+					first: 0,
+					count: 12,
+					base_vertex: 0,
+				},
+				DrawRange::Indices {
+					first: 12,
+					count: 6,
+					base_vertex: 0,
+				},
+				DrawRange::Indices {
+					first: 18,
+					count: 96,
+					base_vertex: 0,
+				},
+			]
+		);
+		assert_eq!(renderer.render_state(), &RenderState::default());
+
+		renderer_2d.delete(renderer.as_mut()).unwrap();
+		assert!(log.live_shaders().is_empty());
+		assert!(log.live_meshes().is_empty());
+		assert_eq!(log.live_textures(), vec![texture]);
+	}
+
+	#[test]
+	fn failed_flush_drops_the_batch() {
+		let renderer = RecordingRenderer::new();
+		let log = renderer.log();
+		let mut renderer: Box<dyn Renderer> = box renderer;
+		let mut font = Font::bitmap(renderer.as_mut()).unwrap();
+		renderer.delete_texture(font.texture()).unwrap();
+
+		let mut renderer_2d = Renderer2D::new();
+		renderer_2d.draw_quad((0.0, 0.0), (10.0, 10.0), [1.0; 4]);
+		renderer_2d.draw_text(&mut font, "A", (0.0, 0.0), &TextOptions::new(5.0), [1.0; 4]);
+		assert!(renderer_2d.flush(renderer.as_mut()).is_err());
+
+		// nothing of the failed batch is uploaded or drawn again
+		log.take_commands();
+		renderer_2d.flush(renderer.as_mut()).unwrap();
+		assert!(log.take_commands().is_empty());
+
+		renderer_2d.draw_quad((0.0, 0.0), (10.0, 10.0), [1.0; 4]);
+		renderer_2d.flush(renderer.as_mut()).unwrap();
+		let ranges = log
+			.take_commands()
+			.into_iter()
+			.filter_map(|c| match c {
+				RenderCommand::Render { params, .. } => Some(params.range),
+				_ => None,
+			})
+			.collect::<Vec<_>>();
+		assert_eq!(
+			ranges,
+			vec![DrawRange::Indices {
+				first: 0,
+				count: 6,
+				base_vertex: 0,
+			}]
+		);
+		renderer_2d.delete(renderer.as_mut()).unwrap();
+	}
+}
@@ -1,12 +1,18 @@
 use whm::{
 	Float,
+	Matrix2,
+	Matrix3,
+	Matrix4,
+	Vector2,
 	Vector3,
 	Vector4,
 };
 
+use super::texture::TextureHandle;
+
 use std::fmt;
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct ShaderHandle {
 	pub id: u32
 }
@@ -31,9 +37,14 @@ impl Drop for ShaderHandle {
 	}
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ShaderType {
 	Vertex,
 	Fragment,
+	Geometry,
+	TessControl,
+	TessEvaluation,
+	Compute,
 }
 
 impl fmt::Display for ShaderType {
@@ -41,16 +52,60 @@ impl fmt::Display for ShaderType {
 		write!(f, "{}", match self {
 			ShaderType::Vertex => "Vertex",
 			ShaderType::Fragment => "Fragment",
+			ShaderType::Geometry => "Geometry",
+			ShaderType::TessControl => "Tessellation Control",
+			ShaderType::TessEvaluation => "Tessellation Evaluation",
+			ShaderType::Compute => "Compute",
 		})
 	}
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub enum ShaderUniform {
 	Float(Float),
+	Vector2(Vector2),
 	Vector3(Vector3),
 	Vector4(Vector4),
 	I32(i32),
+	IVector2([i32; 2]),
+	IVector3([i32; 3]),
+	IVector4([i32; 4]),
 	U32(u32),
+	UVector2([u32; 2]),
+	UVector3([u32; 3]),
+	UVector4([u32; 4]),
+	Bool(bool),
+	// the flag transposes the matrix on upload
+	Matrix2(Matrix2, bool),
+	Matrix3(Matrix3, bool),
+	Matrix4(Matrix4, bool),
+	FloatArray(Vec<Float>),
+	Vector2Array(Vec<Vector2>),
+	Vector3Array(Vec<Vector3>),
+	Vector4Array(Vec<Vector4>),
+	I32Array(Vec<i32>),
+	IVector2Array(Vec<[i32; 2]>),
+	IVector3Array(Vec<[i32; 3]>),
+	IVector4Array(Vec<[i32; 4]>),
+	U32Array(Vec<u32>),
+	UVector2Array(Vec<[u32; 2]>),
+	UVector3Array(Vec<[u32; 3]>),
+	UVector4Array(Vec<[u32; 4]>),
+	BoolArray(Vec<bool>),
+	Matrix2Array(Vec<Matrix2>, bool),
+	Matrix3Array(Vec<Matrix3>, bool),
+	Matrix4Array(Vec<Matrix4>, bool),
+	// texture unit
+	Sampler(i32),
+	SamplerArray(Vec<i32>),
+	// the renderer picks a unit and binds the texture to it when rendering
+	Texture(TextureHandle),
+}
+
+impl Into<ShaderUniform> for TextureHandle {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::Texture(self)
+	}
 }
 
 impl Into<ShaderUniform> for Float {
@@ -59,6 +114,12 @@ impl Into<ShaderUniform> for Float {
 	}
 }
 
+impl Into<ShaderUniform> for Vector2 {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::Vector2(self)
+	}
+}
+
 impl Into<ShaderUniform> for Vector3 {
 	fn into(self) -> ShaderUniform {
 		ShaderUniform::Vector3(self)
@@ -77,8 +138,301 @@ impl Into<ShaderUniform> for i32 {
 	}
 }
 
+impl Into<ShaderUniform> for [i32; 2] {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::IVector2(self)
+	}
+}
+
+impl Into<ShaderUniform> for [i32; 3] {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::IVector3(self)
+	}
+}
+
+impl Into<ShaderUniform> for [i32; 4] {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::IVector4(self)
+	}
+}
+
 impl Into<ShaderUniform> for u32 {
 	fn into(self) -> ShaderUniform {
 		ShaderUniform::U32(self)
 	}
 }
+
+impl Into<ShaderUniform> for [u32; 2] {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::UVector2(self)
+	}
+}
+
+impl Into<ShaderUniform> for [u32; 3] {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::UVector3(self)
+	}
+}
+
+impl Into<ShaderUniform> for [u32; 4] {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::UVector4(self)
+	}
+}
+
+impl Into<ShaderUniform> for bool {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::Bool(self)
+	}
+}
+
+impl Into<ShaderUniform> for Matrix2 {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::Matrix2(self, false)
+	}
+}
+
+impl Into<ShaderUniform> for Matrix3 {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::Matrix3(self, false)
+	}
+}
+
+impl Into<ShaderUniform> for Matrix4 {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::Matrix4(self, false)
+	}
+}
+
+impl Into<ShaderUniform> for Vec<Float> {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::FloatArray(self)
+	}
+}
+
+impl Into<ShaderUniform> for &[Float] {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::FloatArray(self.to_vec())
+	}
+}
+
+impl Into<ShaderUniform> for Vec<Vector2> {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::Vector2Array(self)
+	}
+}
+
+impl Into<ShaderUniform> for &[Vector2] {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::Vector2Array(self.to_vec())
+	}
+}
+
+impl Into<ShaderUniform> for Vec<Vector3> {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::Vector3Array(self)
+	}
+}
+
+impl Into<ShaderUniform> for &[Vector3] {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::Vector3Array(self.to_vec())
+	}
+}
+
+impl Into<ShaderUniform> for Vec<Vector4> {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::Vector4Array(self)
+	}
+}
+
+impl Into<ShaderUniform> for &[Vector4] {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::Vector4Array(self.to_vec())
+	}
+}
+
+impl Into<ShaderUniform> for Vec<i32> {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::I32Array(self)
+	}
+}
+
+impl Into<ShaderUniform> for &[i32] {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::I32Array(self.to_vec())
+	}
+}
+
+impl Into<ShaderUniform> for Vec<[i32; 2]> {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::IVector2Array(self)
+	}
+}
+
+impl Into<ShaderUniform> for &[[i32; 2]] {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::IVector2Array(self.to_vec())
+	}
+}
+
+impl Into<ShaderUniform> for Vec<[i32; 3]> {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::IVector3Array(self)
+	}
+}
+
+impl Into<ShaderUniform> for &[[i32; 3]] {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::IVector3Array(self.to_vec())
+	}
+}
+
+impl Into<ShaderUniform> for Vec<[i32; 4]> {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::IVector4Array(self)
+	}
+}
+
+impl Into<ShaderUniform> for &[[i32; 4]] {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::IVector4Array(self.to_vec())
+	}
+}
+
+impl Into<ShaderUniform> for Vec<u32> {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::U32Array(self)
+	}
+}
+
+impl Into<ShaderUniform> for &[u32] {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::U32Array(self.to_vec())
+	}
+}
+
+impl Into<ShaderUniform> for Vec<[u32; 2]> {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::UVector2Array(self)
+	}
+}
+
+impl Into<ShaderUniform> for &[[u32; 2]] {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::UVector2Array(self.to_vec())
+	}
+}
+
+impl Into<ShaderUniform> for Vec<[u32; 3]> {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::UVector3Array(self)
+	}
+}
+
+impl Into<ShaderUniform> for &[[u32; 3]] {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::UVector3Array(self.to_vec())
+	}
+}
+
+impl Into<ShaderUniform> for Vec<[u32; 4]> {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::UVector4Array(self)
+	}
+}
+
+impl Into<ShaderUniform> for &[[u32; 4]] {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::UVector4Array(self.to_vec())
+	}
+}
+
+impl Into<ShaderUniform> for Vec<bool> {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::BoolArray(self)
+	}
+}
+
+impl Into<ShaderUniform> for &[bool] {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::BoolArray(self.to_vec())
+	}
+}
+
+impl Into<ShaderUniform> for Vec<Matrix2> {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::Matrix2Array(self, false)
+	}
+}
+
+impl Into<ShaderUniform> for &[Matrix2] {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::Matrix2Array(self.to_vec(), false)
+	}
+}
+
+impl Into<ShaderUniform> for Vec<Matrix3> {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::Matrix3Array(self, false)
+	}
+}
+
+impl Into<ShaderUniform> for &[Matrix3] {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::Matrix3Array(self.to_vec(), false)
+	}
+}
+
+impl Into<ShaderUniform> for Vec<Matrix4> {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::Matrix4Array(self, false)
+	}
+}
+
+impl Into<ShaderUniform> for &[Matrix4] {
+	fn into(self) -> ShaderUniform {
+		ShaderUniform::Matrix4Array(self.to_vec(), false)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn uniform(value: impl Into<ShaderUniform>) -> ShaderUniform {
+		value.into()
+	}
+
+	#[test]
+	fn uniform_conversions() {
+		let v2 = Vector2::new(1.0, 2.0);
+		let v3 = Vector3::new(1.0, 2.0, 3.0);
+		let m2 = Matrix2::new(1.0, 0.0, 0.0, 1.0);
+		let m4 = Matrix4::new(
+			1.0, 0.0, 0.0, 0.0,
+			0.0, 1.0, 0.0, 0.0,
+			0.0, 0.0, 1.0, 0.0,
+			0.0, 0.0, 0.0, 1.0,
+		);
+
+		assert_eq!(uniform(0.5), ShaderUniform::Float(0.5));
+		assert_eq!(uniform(v3), ShaderUniform::Vector3(v3));
+		assert_eq!(uniform(-1i32), ShaderUniform::I32(-1));
+		assert_eq!(uniform([1i32, 2, 3]), ShaderUniform::IVector3([1, 2, 3]));
+		assert_eq!(uniform(7u32), ShaderUniform::U32(7));
+		assert_eq!(uniform([1u32, 2]), ShaderUniform::UVector2([1, 2]));
+		assert_eq!(uniform(true), ShaderUniform::Bool(true));
+		assert_eq!(uniform(TextureHandle::new(3)), ShaderUniform::Texture(TextureHandle::new(3)));
+
+		// matrices are uploaded as they are unless asked to transpose
+		assert_eq!(uniform(m2), ShaderUniform::Matrix2(m2, false));
+		assert_eq!(uniform(m4), ShaderUniform::Matrix4(m4, false));
+		assert_eq!(uniform(vec![m4, m4]), ShaderUniform::Matrix4Array(vec![m4, m4], false));
+
+		assert_eq!(uniform(vec![0.5, 1.5]), ShaderUniform::FloatArray(vec![0.5, 1.5]));
+		assert_eq!(uniform(&[v2, v2][..]), ShaderUniform::Vector2Array(vec![v2, v2]));
+		assert_eq!(uniform(&[[1u32, 2, 3, 4]][..]), ShaderUniform::UVector4Array(vec![[1, 2, 3, 4]]));
+		assert_eq!(uniform(vec![true, false]), ShaderUniform::BoolArray(vec![true, false]));
+	}
+}
@@ -0,0 +1,173 @@
+use super::error::RendererError;
+
+use whm::Float;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextureHandle {
+	pub id: u32,
+}
+
+impl TextureHandle {
+	pub fn new(id: u32) -> Self {
+		Self { id }
+	}
+	pub fn none() -> Self {
+		Self { id: 0 }
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureFormat {
+	R8,
+	Rg8,
+	Rgb8,
+	Rgba8,
+	R16F,
+	Rgb16F,
+	Rgba16F,
+	R32F,
+	Rgba32F,
+	// read as an unsigned integer, e.g. object ids for picking
+	R32UI,
+}
+
+impl TextureFormat {
+	pub fn bytes_per_pixel(&self) -> usize {
+		match self {
+			TextureFormat::R8 => 1,
+			TextureFormat::Rg8 => 2,
+			TextureFormat::Rgb8 => 3,
+			TextureFormat::Rgba8 => 4,
+			TextureFormat::R16F => 2,
+			TextureFormat::Rgb16F => 6,
+			TextureFormat::Rgba16F => 8,
+			TextureFormat::R32F => 4,
+			TextureFormat::Rgba32F => 16,
+			TextureFormat::R32UI => 4,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureFilter {
+	Nearest,
+	Linear,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureWrap {
+	Repeat,
+	MirroredRepeat,
+	ClampToEdge,
+	ClampToBorder,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SamplerState {
+	pub min_filter: TextureFilter,
+	pub mag_filter: TextureFilter,
+	pub wrap_s: TextureWrap,
+	pub wrap_t: TextureWrap,
+	pub mipmaps: bool,
+	// 1.0 turns anisotropic filtering off
+	pub anisotropy: Float,
+}
+
+impl SamplerState {
+	pub fn nearest() -> Self {
+		Self {
+			min_filter: TextureFilter::Nearest,
+			mag_filter: TextureFilter::Nearest,
+			mipmaps: false,
+			..Default::default()
+		}
+	}
+}
+
+impl Default for SamplerState {
+	fn default() -> Self {
+		Self {
+			min_filter: TextureFilter::Linear,
+			mag_filter: TextureFilter::Linear,
+			wrap_s: TextureWrap::Repeat,
+			wrap_t: TextureWrap::Repeat,
+			mipmaps: true,
+			anisotropy: 1.0,
+		}
+	}
+}
+
+pub(crate) fn check_region(
+	texture_size: (u32, u32),
+	format: TextureFormat,
+	offset: (u32, u32),
+	size: (u32, u32),
+	data: &[u8],
+) -> Result<(), RendererError> {
+	// an end past u32::MAX is out of bounds too
+	let inside = |offset: u32, size: u32, texture_size: u32| match offset.checked_add(size) {
+		Some(end) => end <= texture_size,
+		None => false,
+	};
+	if !inside(offset.0, size.0, texture_size.0) || !inside(offset.1, size.1, texture_size.1) {
+		return Err(RendererError::TextureRegionOutOfBounds {
+			offset,
+			size,
+			texture_size,
+		});
+	}
+	let expected = size.0 as usize * size.1 as usize * format.bytes_per_pixel();
+	if data.len() != expected {
+		return Err(RendererError::TextureDataMismatch {
+			size: data.len(),
+			expected,
+		});
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn texture_regions() {
+		let rgba = |size: (u32, u32)| vec![0; size.0 as usize * size.1 as usize * 4];
+		let check =
+			|offset, size| check_region((8, 4), TextureFormat::Rgba8, offset, size, &rgba(size));
+
+		assert!(check((0, 0), (8, 4)).is_ok());
+		assert!(check((6, 3), (2, 1)).is_ok());
+		assert!(check((0, 0), (0, 0)).is_ok());
+		match check((7, 0), (2, 1)) {
+			Err(RendererError::TextureRegionOutOfBounds {
+				offset,
+				size,
+				texture_size,
+			}) => {
+				assert_eq!((offset, size, texture_size), ((7, 0), (2, 1), (8, 4)));
+			}
+			result => panic!("expected out of bounds, got {:?}", result),
+		}
+		assert!(check((0, 4), (1, 1)).is_err());
+		// offset + size doesn't fit in a u32
+		let overflow = check_region((8, 4), TextureFormat::Rgba8, (u32::MAX, 0), (2, 1), &[]);
+		match overflow {
+			Err(RendererError::TextureRegionOutOfBounds { .. }) => {}
+			result => panic!("expected out of bounds, got {:?}", result),
+		}
+		assert!(
+			check_region((8, 4), TextureFormat::R8, (0, u32::MAX), (1, u32::MAX), &[]).is_err()
+		);
+
+		// the data has to be exactly width * height * bytes per pixel
+		assert!(check_region((8, 4), TextureFormat::Rgb16F, (0, 0), (2, 2), &[0; 24]).is_ok());
+		match check_region((8, 4), TextureFormat::Rgb16F, (0, 0), (2, 2), &[0; 23]) {
+			Err(RendererError::TextureDataMismatch { size, expected }) => {
+				assert_eq!((size, expected), (23, 24));
+			}
+			result => panic!("expected a data mismatch, got {:?}", result),
+		}
+		assert!(check_region((8, 4), TextureFormat::R8, (0, 0), (2, 2), &[0; 16]).is_err());
+	}
+}
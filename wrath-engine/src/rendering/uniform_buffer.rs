@@ -0,0 +1,228 @@
+use super::{error::RendererError, Renderer};
+
+use whm::{Float, Matrix4, Vector2, Vector3, Vector4};
+
+use std::mem;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UniformBufferHandle {
+	pub id: u32,
+}
+
+impl UniformBufferHandle {
+	pub fn new(id: u32) -> Self {
+		Self { id }
+	}
+	pub fn none() -> Self {
+		Self { id: 0 }
+	}
+}
+
+pub struct UniformBlockLayout {
+	pub types: Vec<UniformBlockElement>,
+	pub offsets: Vec<usize>,
+	// end of the last element, the buffer itself is padded to 16 bytes
+	pub size: usize,
+}
+
+impl UniformBlockLayout {
+	pub fn new(elements: &[UniformBlockElement]) -> Self {
+		let mut offset = 0;
+		let mut types = Vec::with_capacity(elements.len());
+		let mut offsets = Vec::with_capacity(elements.len());
+		for element in elements {
+			offset = align(offset, element.alignment());
+			types.push(*element);
+			offsets.push(offset);
+			offset += element.size();
+		}
+		Self {
+			types,
+			offsets,
+			size: offset,
+		}
+	}
+}
+
+// std140 rules; arrays only come in vec4 and mat4 flavours because every
+// other array element would be padded to 16 bytes anyway
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UniformBlockElement {
+	Float,
+	I32,
+	U32,
+	Bool,
+	Vector2,
+	Vector3,
+	Vector4,
+	Matrix2,
+	Matrix3,
+	Matrix4,
+	Vector4Array(usize),
+	Matrix4Array(usize),
+}
+
+impl UniformBlockElement {
+	pub fn alignment(&self) -> usize {
+		match self {
+			UniformBlockElement::Float
+			| UniformBlockElement::I32
+			| UniformBlockElement::U32
+			| UniformBlockElement::Bool => 4,
+			UniformBlockElement::Vector2 => 8,
+			_ => 16,
+		}
+	}
+	pub fn size(&self) -> usize {
+		match self {
+			UniformBlockElement::Float
+			| UniformBlockElement::I32
+			| UniformBlockElement::U32
+			| UniformBlockElement::Bool => 4,
+			UniformBlockElement::Vector2 => 8,
+			UniformBlockElement::Vector3 => 12,
+			UniformBlockElement::Vector4 => 16,
+			// matrix columns are padded to a vec4 each
+			UniformBlockElement::Matrix2 => 32,
+			UniformBlockElement::Matrix3 => 48,
+			UniformBlockElement::Matrix4 => 64,
+			UniformBlockElement::Vector4Array(len) => 16 * len,
+			UniformBlockElement::Matrix4Array(len) => 64 * len,
+		}
+	}
+}
+
+fn align(offset: usize, alignment: usize) -> usize {
+	(offset + alignment - 1) / alignment * alignment
+}
+
+// implementors have to be #[repr(C)] with the fields laid out as layout()
+// says, the struct is uploaded as raw bytes. use #[derive(UniformBlock)] so the
+// field offsets are checked too, hand written impls only get their size checked
+pub unsafe trait UniformBlock: Copy {
+	fn layout() -> UniformBlockLayout;
+	// where every field is in the struct, None skips the check
+	fn offsets() -> Option<Vec<usize>> {
+		None
+	}
+}
+
+// the types that are laid out the same in a repr(C) struct and in std140, bools
+// and matrices smaller than mat4 aren't and need to be uploaded some other way
+pub trait UniformBlockField {
+	const ELEMENT: UniformBlockElement;
+}
+
+macro_rules! uniform_block_field {
+	($type:ty, $element:ident) => {
+		impl UniformBlockField for $type {
+			const ELEMENT: UniformBlockElement = UniformBlockElement::$element;
+		}
+	};
+}
+
+uniform_block_field!(Float, Float);
+uniform_block_field!(i32, I32);
+uniform_block_field!(u32, U32);
+uniform_block_field!([Float; 2], Vector2);
+uniform_block_field!([Float; 3], Vector3);
+uniform_block_field!([Float; 4], Vector4);
+uniform_block_field!(Vector2, Vector2);
+uniform_block_field!(Vector3, Vector3);
+uniform_block_field!(Vector4, Vector4);
+uniform_block_field!([[Float; 4]; 4], Matrix4);
+uniform_block_field!(Matrix4, Matrix4);
+
+impl dyn Renderer + '_ {
+	pub fn create_uniform_block<T: UniformBlock>(
+		&mut self,
+		name: &str,
+		value: &T,
+	) -> Result<UniformBufferHandle, RendererError> {
+		let layout = T::layout();
+		if mem::size_of::<T>() != layout.size {
+			return Err(RendererError::UniformBlockSizeMismatch {
+				name: name.into(),
+				size: mem::size_of::<T>(),
+				expected: layout.size,
+			});
+		}
+		if let Some(offsets) = T::offsets() {
+			if offsets != layout.offsets {
+				return Err(RendererError::UniformBlockLayoutMismatch {
+					name: name.into(),
+					offsets,
+					expected: layout.offsets,
+				});
+			}
+		}
+
+		let handle = self.create_uniform_buffer(name, &layout)?;
+		self.update_uniform_block(handle, value)?;
+		Ok(handle)
+	}
+	pub fn update_uniform_block<T: UniformBlock>(
+		&mut self,
+		handle: UniformBufferHandle,
+		value: &T,
+	) -> Result<(), RendererError> {
+		let bytes = unsafe {
+			std::slice::from_raw_parts(value as *const T as *const u8, mem::size_of::<T>())
+		};
+		self.update_uniform_buffer(handle, bytes)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::RecordingRenderer;
+
+	#[derive(Clone, Copy, crate::UniformBlock)]
+	#[repr(C)]
+	struct TestBlock {
+		color: [f32; 3],
+		intensity: f32,
+		offset: [f32; 2],
+	}
+
+	// std140 moves the vec3 to offset 16
+	#[derive(Clone, Copy, crate::UniformBlock)]
+	#[repr(C)]
+	struct PaddedBlock {
+		intensity: f32,
+		color: [f32; 3],
+	}
+
+	// the way overlays get the renderer
+	fn create(renderer: &mut dyn Renderer, block: &TestBlock) -> UniformBufferHandle {
+		let handle = renderer.create_uniform_block("Light", block).unwrap();
+		renderer.update_uniform_block(handle, block).unwrap();
+		handle
+	}
+
+	#[test]
+	fn uniform_block() {
+		let layout = TestBlock::layout();
+		assert_eq!(layout.offsets, vec![0, 12, 16]);
+		assert_eq!(layout.size, 24);
+		assert_eq!(TestBlock::offsets(), Some(layout.offsets));
+
+		let mut renderer: Box<dyn Renderer> = box RecordingRenderer::new();
+		let block = TestBlock {
+			color: [1.0, 0.5, 0.0],
+			intensity: 2.0,
+			offset: [0.0, 0.0],
+		};
+		let handle = create(renderer.as_mut(), &block);
+		assert!(renderer.update_uniform_buffer(handle, &[0; 4]).is_err());
+
+		assert_eq!(PaddedBlock::offsets(), Some(vec![0, 4]));
+		assert_eq!(PaddedBlock::layout().offsets, vec![0, 16]);
+		let padded = PaddedBlock {
+			intensity: 1.0,
+			color: [1.0; 3],
+		};
+		assert!(renderer.create_uniform_block("Padded", &padded).is_err());
+	}
+}
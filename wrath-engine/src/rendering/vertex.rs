@@ -0,0 +1,188 @@
+use super::{
+	error::RendererError,
+	mesh::{
+		BufferElement, BufferLayout, BufferUsage, Indices, InstanceBufferHandle, MeshHandle,
+		Vertices,
+	},
+	Renderer,
+};
+
+use whm::{Float, Matrix4, Vector2, Vector3, Vector4};
+
+use std::mem::size_of;
+
+// implementors have to be #[repr(C)] without padding and laid out as
+// layout() says, use #[derive(Vertex)] instead of implementing it by hand
+pub unsafe trait Vertex: Copy {
+	fn layout() -> BufferLayout;
+}
+
+pub trait VertexAttribute {
+	const ELEMENT: BufferElement;
+	const SIZE: usize;
+}
+
+// marks 8 and 16 bit integer attributes as read in 0..1 or -1..1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(transparent)]
+pub struct Normalized<T>(pub T);
+
+macro_rules! vertex_attribute {
+	($type:ty, $element:ident) => {
+		impl VertexAttribute for $type {
+			const ELEMENT: BufferElement = BufferElement::$element;
+			const SIZE: usize = size_of::<$type>();
+		}
+	};
+}
+
+vertex_attribute!(Float, Float);
+vertex_attribute!([Float; 2], Vector2);
+vertex_attribute!([Float; 3], Vector3);
+vertex_attribute!([Float; 4], Vector4);
+vertex_attribute!(Vector2, Vector2);
+vertex_attribute!(Vector3, Vector3);
+vertex_attribute!(Vector4, Vector4);
+vertex_attribute!([[Float; 4]; 4], Matrix4);
+vertex_attribute!(Matrix4, Matrix4);
+vertex_attribute!(Normalized<[u8; 4]>, U8x4Norm);
+vertex_attribute!(Normalized<[i8; 4]>, I8x4Norm);
+vertex_attribute!(Normalized<[u16; 2]>, U16x2Norm);
+vertex_attribute!(Normalized<[i16; 2]>, I16x2Norm);
+vertex_attribute!([u8; 4], U8x4);
+vertex_attribute!([i8; 4], I8x4);
+vertex_attribute!([u16; 2], U16x2);
+vertex_attribute!([i16; 2], I16x2);
+vertex_attribute!(u32, U32);
+vertex_attribute!(i32, I32);
+
+impl Vertices {
+	pub fn from_slice<V: Vertex>(vertices: &[V]) -> Self {
+		let bytes = unsafe {
+			std::slice::from_raw_parts(
+				vertices.as_ptr() as *const u8,
+				std::mem::size_of_val(vertices),
+			)
+		};
+		Self::from_bytes(bytes.to_vec())
+	}
+}
+
+impl dyn Renderer + '_ {
+	pub fn create_mesh<V: Vertex>(
+		&mut self,
+		vertices: &[V],
+		indices: &Indices,
+	) -> Result<MeshHandle, RendererError> {
+		self.create_mesh_with_usage(vertices, indices, BufferUsage::Static)
+	}
+	pub fn create_mesh_with_usage<V: Vertex>(
+		&mut self,
+		vertices: &[V],
+		indices: &Indices,
+		usage: BufferUsage,
+	) -> Result<MeshHandle, RendererError> {
+		self.create_mesh_from_layout(
+			&Vertices::from_slice(vertices),
+			&V::layout(),
+			indices,
+			usage,
+		)
+	}
+	pub fn update_vertices_from_slice<V: Vertex>(
+		&mut self,
+		handle: MeshHandle,
+		offset: usize,
+		vertices: &[V],
+	) -> Result<(), RendererError> {
+		self.update_vertices(handle, offset, &Vertices::from_slice(vertices))
+	}
+	// the vertex layout is used per instance
+	pub fn create_instance_buffer_from_slice<V: Vertex>(
+		&mut self,
+		instances: &[V],
+		usage: BufferUsage,
+	) -> Result<InstanceBufferHandle, RendererError> {
+		let layout = V::layout();
+		self.create_instance_buffer(
+			&Vertices::from_slice(instances),
+			&BufferLayout::per_instance(&layout.types),
+			usage,
+		)
+	}
+	pub fn update_instance_buffer_from_slice<V: Vertex>(
+		&mut self,
+		handle: InstanceBufferHandle,
+		offset: usize,
+		instances: &[V],
+	) -> Result<(), RendererError> {
+		self.update_instance_buffer(handle, offset, &Vertices::from_slice(instances))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{RecordingRenderer, RenderCommand, Vertex};
+
+	#[derive(Clone, Copy, Vertex)]
+	#[repr(C)]
+	struct TestVertex {
+		position: [f32; 3],
+		uv: Normalized<[i16; 2]>,
+		color: Normalized<[u8; 4]>,
+		bones: [u8; 4],
+	}
+
+	#[test]
+	fn derived_vertex() {
+		let layout = TestVertex::layout();
+		assert_eq!(
+			layout.types,
+			vec![
+				BufferElement::Vector3,
+				BufferElement::I16x2Norm,
+				BufferElement::U8x4Norm,
+				BufferElement::U8x4,
+			]
+		);
+		assert_eq!(layout.stride, std::mem::size_of::<TestVertex>());
+	}
+
+	// the way overlays get the renderer
+	fn upload(renderer: &mut dyn Renderer, vertices: &[TestVertex]) {
+		let mesh = renderer
+			.create_mesh(vertices, &Indices::U8(vec![0, 1]))
+			.unwrap();
+		renderer
+			.update_vertices_from_slice(mesh, 1, &vertices[..1])
+			.unwrap();
+	}
+
+	#[test]
+	fn typed_helpers() {
+		let renderer = RecordingRenderer::new();
+		let log = renderer.log();
+		let mut renderer: Box<dyn Renderer> = box renderer;
+		let vertex = TestVertex {
+			position: [0.0; 3],
+			uv: Normalized([0; 2]),
+			color: Normalized([255; 4]),
+			bones: [0; 4],
+		};
+		upload(renderer.as_mut(), &[vertex; 2]);
+
+		match &log.commands()[0] {
+			RenderCommand::CreateMesh {
+				vertices_size,
+				layout,
+				..
+			} => {
+				assert_eq!(*vertices_size, 2 * std::mem::size_of::<TestVertex>());
+				assert_eq!(*layout, TestVertex::layout().types);
+			}
+			_ => panic!("expected mesh creation"),
+		}
+		assert!(log.take_errors().is_empty());
+	}
+}
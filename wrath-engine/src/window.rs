@@ -1,4 +1,4 @@
-use crate::Event;
+use crate::{BackendError, Event, WindowBackend};
 
 pub trait Window {
 	fn set_title(&mut self, title: String);
@@ -14,6 +14,19 @@ pub struct WindowProps {
 	pub size: (u32, u32),
 }
 
-pub fn create(props: WindowProps) -> Box<dyn Window> {
-	box crate::platform::glutin_window::GlutinWindow::new(props)
+pub fn create(backend: WindowBackend, props: WindowProps) -> Result<Box<dyn Window>, BackendError> {
+	match backend {
+		#[cfg(feature = "glutin")]
+		WindowBackend::Glutin => Ok(box crate::platform::glutin_window::GlutinWindow::new(props)),
+		#[cfg(feature = "glfw")]
+		WindowBackend::Glfw => Ok(box crate::platform::glfw_window::GLFWWindow::new(props)),
+		#[cfg(feature = "x11")]
+		WindowBackend::X11 => Ok(box crate::platform::x11_window::X11Window::new(props)?),
+		#[cfg(feature = "headless")]
+		WindowBackend::Headless => Ok(box crate::platform::headless_window::HeadlessWindow::new(
+			props,
+		)),
+		#[allow(unreachable_patterns)]
+		backend => Err(BackendError::WindowBackendNotCompiled(backend)),
+	}
 }